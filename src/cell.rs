@@ -0,0 +1,139 @@
+//! Value which can be initialized through shared reference.
+
+use core::cell::UnsafeCell;
+use core::panic::Location;
+use ::{handler, Access, AccessContext, DefaultExceptionHandler, ExceptionHandler, State};
+
+/// Represents a value that is initialized once through shared reference.
+///
+/// This is useful when the code initializing the value only has `&self`, e.g. in callbacks. The
+/// value can be set only once while it's shared, so that references to it stay valid. Attempt to
+/// set it again calls `ExceptionHandler::bad_reinit()`. Dereferencing uninitialized value calls
+/// the ExceptionHandler the same way `DangerousOption` does.
+///
+/// This type is not `Sync`. Use `DangerousOnce` to share late-initialized values between threads.
+pub struct DangerousCell<T, H: ExceptionHandler = DefaultExceptionHandler> {
+    value: UnsafeCell<Option<T>>,
+    _handler: core::marker::PhantomData<H>,
+}
+
+impl<T, H: ExceptionHandler> DangerousCell<T, H> {
+    /// Creates initialized value.
+    pub const fn new(val: T) -> Self {
+        DangerousCell { value: UnsafeCell::new(Some(val)), _handler: core::marker::PhantomData }
+    }
+
+    /// Creates uninitialized value.
+    pub const fn new_uninitialized() -> Self {
+        DangerousCell { value: UnsafeCell::new(None), _handler: core::marker::PhantomData }
+    }
+
+    /// Initializes the value, failing if it was already initialized.
+    #[track_caller]
+    pub fn set(this: &Self, val: T) {
+        if DangerousCell::is_initialized(this) {
+            this.fail(Access::Init, State::Initialized);
+        }
+
+        // There are no references into uninitialized value and writing `Option` doesn't run
+        // any code which could create them.
+        unsafe {
+            *this.value.get() = Some(val);
+        }
+    }
+
+    /// Returns `true` if the value is initialized.
+    pub fn is_initialized(this: &Self) -> bool {
+        DangerousCell::try(this).is_some()
+    }
+
+    /// Non-panicking version of deref, which returns `None`, if value is uninitialized.
+    pub fn try(this: &Self) -> Option<&T> {
+        // Initialized value is never modified through shared reference.
+        unsafe { (*this.value.get()).as_ref() }
+    }
+
+    /// Non-panicking version of deref_mut, which returns `None`, if value is uninitialized.
+    pub fn try_mut(this: &mut Self) -> Option<&mut T> {
+        this.value.get_mut().as_mut()
+    }
+
+    /// Takes out the value, leaving the cell uninitialized. This requires exclusive access, so
+    /// no references to the value may exist.
+    pub fn take_checked(this: &mut Self) -> Option<T> {
+        this.value.get_mut().take()
+    }
+
+    #[track_caller]
+    fn fail(&self, access: Access, state: State) -> ! {
+        let context = AccessContext::new::<T>("DangerousCell", access, Location::caller(), state, None);
+        handler::dispatch::<H>(&context)
+    }
+}
+
+impl<T, H: ExceptionHandler> core::ops::Deref for DangerousCell<T, H> {
+    type Target = T;
+
+    #[track_caller]
+    fn deref(&self) -> &Self::Target {
+        match DangerousCell::try(self) {
+            Some(val) => val,
+            None => self.fail(Access::Deref, State::Uninitialized),
+        }
+    }
+}
+
+impl<T, H: ExceptionHandler> core::ops::DerefMut for DangerousCell<T, H> {
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if !DangerousCell::is_initialized(self) {
+            self.fail(Access::DerefMut, State::Uninitialized);
+        }
+        match *self.value.get_mut() {
+            Some(ref mut val) => val,
+            None => unreachable!(),
+        }
+    }
+}
+
+impl<T: core::fmt::Debug, H: ExceptionHandler> core::fmt::Debug for DangerousCell<T, H> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match DangerousCell::try(self) {
+            Some(val) => core::fmt::Debug::fmt(val, f),
+            None => write!(f, "<uninitialized>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::DangerousCell;
+
+    struct Callbacks {
+        name: DangerousCell<&'static str>,
+    }
+
+    impl Callbacks {
+        fn on_connect(&self, name: &'static str) {
+            DangerousCell::set(&self.name, name);
+        }
+    }
+
+    #[test]
+    fn set() {
+        let callbacks = Callbacks { name: DangerousCell::new_uninitialized() };
+        assert!(DangerousCell::try(&callbacks.name).is_none());
+        callbacks.on_connect("foo");
+        assert_eq!(*callbacks.name, "foo");
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempt to initialize already initialized DangerousCell<&str>")]
+    fn reinit() {
+        let callbacks = Callbacks { name: DangerousCell::new_uninitialized() };
+        callbacks.on_connect("foo");
+        let name = &*callbacks.name;
+        callbacks.on_connect("bar");
+        assert_eq!(*name, "foo");
+    }
+}
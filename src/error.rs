@@ -0,0 +1,96 @@
+//! Error returned by fallible accessors.
+
+use core::fmt;
+use ::{Provenance, State};
+
+/// The error returned when accessing uninitialized `DangerousOption` using `get()`, `get_mut()`
+/// or `take()`.
+///
+/// Implements `std::error::Error` if the `std` feature is enabled.
+#[derive(Debug, Copy, Clone)]
+pub struct Uninitialized {
+    type_name: &'static str,
+    label: Option<&'static str>,
+    state: State,
+    provenance: Option<Provenance>,
+}
+
+impl Uninitialized {
+    pub(crate) fn new<T: ?Sized>(state: State, label: Option<&'static str>, provenance: Option<Provenance>) -> Self {
+        Uninitialized { type_name: core::any::type_name::<T>(), label, state, provenance }
+    }
+
+    /// The name of the type of the value, as returned by `core::any::type_name()`.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// The label of the value, if it has one (requires `labels` feature).
+    pub fn label(&self) -> Option<&'static str> {
+        self.label
+    }
+
+    /// The state of the value at the time of the access.
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// The place where the value became uninitialized, if it's tracked.
+    pub fn provenance(&self) -> Option<Provenance> {
+        self.provenance
+    }
+}
+
+impl fmt::Display for Uninitialized {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.state == State::Taken || self.state == State::Lost {
+            write!(f, "value of ")?;
+        }
+        write!(f, "DangerousOption<{}>", self.type_name)?;
+        if let Some(label) = self.label {
+            write!(f, " `{}`", label)?;
+        }
+        match self.state {
+            State::Taken => write!(f, " was taken out")?,
+            State::Lost => write!(f, " was lost during a panicking update")?,
+            State::Poisoned => write!(f, " is poisoned")?,
+            State::Uninitialized | State::Initialized | State::InitFailed => write!(f, " is uninitialized")?,
+        }
+        if let Some(provenance) = self.provenance {
+            write!(f, ", uninitialized at {}", provenance)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Uninitialized {}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn result() {
+        use ::{DangerousOption, State, Uninitialized};
+        use std::string::ToString;
+
+        fn double(val: &mut DangerousOption<i32>) -> Result<i32, Uninitialized> {
+            *DangerousOption::get_mut(val)? *= 2;
+            DangerousOption::take(val)
+        }
+
+        let mut val = DangerousOption::new(21);
+        assert_eq!(*DangerousOption::get(&val).unwrap(), 21);
+        assert_eq!(double(&mut val).unwrap(), 42);
+        let error = double(&mut val).unwrap_err();
+        assert_eq!(error.type_name(), "i32");
+        #[cfg(feature = "track-state")]
+        assert_eq!(error.state(), State::Taken);
+        #[cfg(feature = "track-state")]
+        assert!(error.to_string().starts_with("value of DangerousOption<i32> was taken out"));
+        #[cfg(not(feature = "track-state"))]
+        assert_eq!(error.state(), State::Uninitialized);
+        #[cfg(not(feature = "track-state"))]
+        assert!(error.to_string().starts_with("DangerousOption<i32> is uninitialized"));
+        assert!(DangerousOption::get(&DangerousOption::<i32>::new_uninitialized()).is_err());
+    }
+}
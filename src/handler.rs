@@ -0,0 +1,463 @@
+//! Exception handling for invalid accesses.
+
+use core::fmt;
+use core::panic::Location;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use ::{Provenance, State};
+
+/// The kind of operation which was attempted on an invalid value.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Access {
+    /// Shared dereference (`Deref`).
+    Deref,
+    /// Mutable dereference (`DerefMut`).
+    DerefMut,
+    /// Taking out the value (`take_unchecked()`).
+    Take,
+    /// Serializing the value (requires `serde` feature).
+    Serialize,
+    /// Initializing the value (`LateInit::init()`, `DangerousOnce::set()`, `DangerousCell::set()`).
+    Init,
+    /// Putting the sentinel value into `DangerousSentinel`.
+    PutSentinel,
+}
+
+/// Describes an invalid access, so that the `ExceptionHandler` can report it.
+#[derive(Debug, Copy, Clone)]
+pub struct AccessContext {
+    container: &'static str,
+    access: Access,
+    type_name: &'static str,
+    label: Option<&'static str>,
+    location: &'static Location<'static>,
+    state: State,
+    provenance: Option<Provenance>,
+}
+
+impl AccessContext {
+    pub(crate) fn new<T: ?Sized>(container: &'static str, access: Access, location: &'static Location<'static>, state: State, provenance: Option<Provenance>) -> Self {
+        AccessContext {
+            container,
+            access,
+            type_name: core::any::type_name::<T>(),
+            label: None,
+            location,
+            state,
+            provenance,
+        }
+    }
+
+    pub(crate) fn with_label(self, label: Option<&'static str>) -> Self {
+        AccessContext { label, ..self }
+    }
+
+    /// The name of the type whose value was accessed, e.g. `DangerousOption` or `LateInit`.
+    pub fn container(&self) -> &'static str {
+        self.container
+    }
+
+    /// The operation which was attempted.
+    pub fn access(&self) -> Access {
+        self.access
+    }
+
+    /// The name of the type of the value, as returned by `core::any::type_name()`.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// The label of the accessed value, if it has one (requires `labels` feature).
+    pub fn label(&self) -> Option<&'static str> {
+        self.label
+    }
+
+    /// The place in the code where the access happened.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// The state of the value at the time of the access.
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// The place where the value became uninitialized, if it's tracked.
+    pub fn provenance(&self) -> Option<Provenance> {
+        self.provenance
+    }
+}
+
+impl fmt::Display for AccessContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let verb = match self.access {
+            Access::Deref | Access::DerefMut => "Dereferenced",
+            Access::Take => "Attempt to take value from",
+            Access::Serialize => "Serialized",
+            Access::Init => "Attempt to initialize",
+            Access::PutSentinel => "Attempt to put sentinel value into",
+        };
+        let adjective = match self.state {
+            State::Uninitialized => "uninitialized ",
+            State::Initialized => "already initialized ",
+            State::Poisoned => "poisoned ",
+            State::Taken | State::InitFailed | State::Lost => "",
+        };
+        write!(f, "{} {}{}<{}>", verb, adjective, self.container, self.type_name)?;
+        if let Some(label) = self.label {
+            write!(f, " `{}`", label)?;
+        }
+        match (self.access, self.state) {
+            (Access::Take, State::Taken) => write!(f, " after it was already taken out")?,
+            (_, State::Taken) => write!(f, " after its value was taken out")?,
+            (_, State::InitFailed) => write!(f, " after its initializer panicked")?,
+            (_, State::Lost) => write!(f, " after its value was lost during a panicking update")?,
+            (_, _) => (),
+        }
+        write!(f, " at {}", self.location)?;
+        if let Some(provenance) = self.provenance {
+            write!(f, ", uninitialized at {}", provenance)?;
+        }
+        Ok(())
+    }
+}
+
+/// The exception handler defining behavior in case `None` is accessed.
+///
+/// All invalid accesses eventually end up in `handle()`, which receives the description of the
+/// access, unless a more specific callback is overridden. By default, `handle()` calls
+/// `bad_deref()` or `bad_take()`, which panic with a generic message. Thus it's usually best to
+/// override `handle()`, like `DefaultExceptionHandler` does, to panic with a message describing
+/// the access.
+///
+/// The callbacks are `#[track_caller]`, so panicking inside of them reports the location of the
+/// invalid access in user code.
+pub trait ExceptionHandler {
+    /// Called when dereferencing of `None` is attempted.
+    #[track_caller]
+    fn bad_deref() -> ! {
+        panic!("Dereferenced uninitialized DangerousOption")
+    }
+
+    /// Called on attempt to take out value from `Some`, if there is `None`.
+    #[track_caller]
+    fn bad_take() -> ! {
+        panic!("Attempt to take value from uninitialized DangerousOption")
+    }
+
+    /// Called on every invalid access, unless a more specific callback is overridden.
+    ///
+    /// The default implementation calls the hook installed using `set_hook()`, if any, and then
+    /// `bad_take()` if taking the value out was attempted or `bad_deref()` if it was accessed
+    /// otherwise. Other invalid operations panic with a message describing the access.
+    #[track_caller]
+    fn handle(context: &AccessContext) -> ! {
+        if let Some(hook) = hook() {
+            hook(context);
+        }
+        match Fallback::of(context.access) {
+            Some(Fallback::Deref) => Self::bad_deref(),
+            Some(Fallback::Take) => Self::bad_take(),
+            None => panic!("{}", context),
+        }
+    }
+
+    /// Called when dereferencing (or serializing) of value that was taken out is attempted
+    /// (requires `track-state` feature).
+    #[track_caller]
+    fn bad_deref_taken(context: &AccessContext) -> ! {
+        Self::handle(context)
+    }
+
+    /// Called on attempt to take out value that was already taken out (requires `track-state`
+    /// feature).
+    #[track_caller]
+    fn bad_take_taken(context: &AccessContext) -> ! {
+        Self::handle(context)
+    }
+
+    /// Called on attempt to initialize a value which can be initialized only once (`LateInit`,
+    /// `DangerousOnce`, `DangerousCell`) when it was already initialized.
+    #[track_caller]
+    fn bad_reinit(context: &AccessContext) -> ! {
+        Self::handle(context)
+    }
+
+    /// Called when accessing a lazily initialized value (`DangerousLazy`) whose initializer
+    /// panicked.
+    #[track_caller]
+    fn bad_init_failed(context: &AccessContext) -> ! {
+        Self::handle(context)
+    }
+
+    /// Called when accessing a value which was lost because the function passed to
+    /// `DangerousOption::replace_with()` panicked (requires `track-state` feature).
+    #[track_caller]
+    fn bad_lost(context: &AccessContext) -> ! {
+        Self::handle(context)
+    }
+
+    /// Called when accessing a value which was poisoned because the function passed to
+    /// `DangerousOption::update()` panicked (requires `track-state` feature).
+    #[track_caller]
+    fn bad_poisoned(context: &AccessContext) -> ! {
+        Self::handle(context)
+    }
+
+    /// Called on attempt to put the value representing uninitialized state into
+    /// `DangerousSentinel`.
+    #[track_caller]
+    fn bad_sentinel(context: &AccessContext) -> ! {
+        Self::handle(context)
+    }
+}
+
+static HOOK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Installs global hook called on invalid accesses, replacing the previous one.
+///
+/// The hook is called by the default implementation of `ExceptionHandler::handle()`, so it applies
+/// to `DefaultExceptionHandler` and all handlers which don't override `handle()`. This allows the
+/// application to install logging or crash reporting once at startup without changing the types.
+/// If the hook returns, the handler panics as usual. The hook may also panic itself to customize
+/// the message; the location of the access is available in the context.
+pub fn set_hook(hook: fn(&AccessContext)) {
+    HOOK.store(hook as *mut (), Ordering::Release);
+}
+
+/// Removes the global hook, returning it.
+///
+/// On targets without atomic compare-and-swap, only atomic loads and stores are available. There,
+/// if `set_hook()` is called concurrently, the new hook may be removed without being returned.
+pub fn take_hook() -> Option<fn(&AccessContext)> {
+    #[cfg(target_has_atomic = "ptr")]
+    let hook = HOOK.swap(core::ptr::null_mut(), Ordering::AcqRel);
+    #[cfg(not(target_has_atomic = "ptr"))]
+    let hook = {
+        let hook = HOOK.load(Ordering::Acquire);
+        HOOK.store(core::ptr::null_mut(), Ordering::Release);
+        hook
+    };
+    from_ptr(hook)
+}
+
+pub(crate) fn hook() -> Option<fn(&AccessContext)> {
+    from_ptr(HOOK.load(Ordering::Acquire))
+}
+
+fn from_ptr(hook: *mut ()) -> Option<fn(&AccessContext)> {
+    if hook.is_null() {
+        None
+    } else {
+        // Only function pointers of this type are stored in HOOK.
+        Some(unsafe { core::mem::transmute::<*mut (), fn(&AccessContext)>(hook) })
+    }
+}
+
+/// Zero-argument callback through which the default `handle()` reports the access.
+#[derive(Copy, Clone)]
+enum Fallback {
+    Deref,
+    Take,
+}
+
+impl Fallback {
+    fn of(access: Access) -> Option<Self> {
+        match access {
+            Access::Deref | Access::DerefMut | Access::Serialize => Some(Fallback::Deref),
+            Access::Take => Some(Fallback::Take),
+            Access::Init | Access::PutSentinel => None,
+        }
+    }
+}
+
+/// Messages of the handler declared using `exception_handler!`, one for each callback.
+///
+/// Only `handle`, `deref` and `take` are read here, the other messages are reported by the
+/// callbacks the macro overrides.
+#[doc(hidden)]
+#[derive(Copy, Clone)]
+pub struct Messages {
+    pub handle: Option<&'static str>,
+    pub deref: Option<&'static str>,
+    pub take: Option<&'static str>,
+    pub deref_taken: Option<&'static str>,
+    pub take_taken: Option<&'static str>,
+    pub reinit: Option<&'static str>,
+    pub init_failed: Option<&'static str>,
+    pub lost: Option<&'static str>,
+    pub poisoned: Option<&'static str>,
+    pub sentinel: Option<&'static str>,
+}
+
+impl Messages {
+    pub const NONE: Messages = Messages {
+        handle: None,
+        deref: None,
+        take: None,
+        deref_taken: None,
+        take_taken: None,
+        reinit: None,
+        init_failed: None,
+        lost: None,
+        poisoned: None,
+        sentinel: None,
+    };
+}
+
+/// Implementation of `handle()` generated by `exception_handler!`.
+///
+/// Picks `deref` or `take` message the same way the default `handle()` picks the callback.
+#[doc(hidden)]
+#[track_caller]
+pub fn handle_with_messages(messages: &Messages, context: &AccessContext) -> ! {
+    let message = match Fallback::of(context.access) {
+        Some(Fallback::Deref) => messages.deref,
+        Some(Fallback::Take) => messages.take,
+        None => None,
+    };
+    handle_with_message(message.or(messages.handle), context)
+}
+
+/// Implementation of the callbacks generated by `exception_handler!`.
+#[doc(hidden)]
+#[track_caller]
+pub fn handle_with_message(message: Option<&'static str>, context: &AccessContext) -> ! {
+    if let Some(hook) = hook() {
+        hook(context);
+    }
+    match message {
+        Some(message) => {
+            #[cfg(feature = "log")]
+            ::log::error!("{}: {}", message, context);
+            panic!("{}: {}", message, context)
+        },
+        None => {
+            #[cfg(feature = "log")]
+            ::log::error!("{}", context);
+            panic!("{}", context)
+        },
+    }
+}
+
+/// Calls the appropriate callback of the handler.
+#[track_caller]
+pub(crate) fn dispatch<H: ExceptionHandler + ?Sized>(context: &AccessContext) -> ! {
+    match (context.access, context.state) {
+        (Access::Init, _) => H::bad_reinit(context),
+        (Access::PutSentinel, _) => H::bad_sentinel(context),
+        (_, State::InitFailed) => H::bad_init_failed(context),
+        (_, State::Lost) => H::bad_lost(context),
+        (_, State::Poisoned) => H::bad_poisoned(context),
+        (Access::Take, State::Taken) => H::bad_take_taken(context),
+        (_, State::Taken) => H::bad_deref_taken(context),
+        (_, _) => H::handle(context),
+    }
+}
+
+/// This is the default handler for `None` exceptions.
+///
+/// It calls the hook installed using `set_hook()`, if any, and then panics with a message
+/// describing the access.
+pub enum DefaultExceptionHandler {}
+
+impl ExceptionHandler for DefaultExceptionHandler {
+    #[track_caller]
+    fn handle(context: &AccessContext) -> ! {
+        if let Some(hook) = hook() {
+            hook(context);
+        }
+        panic!("{}", context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn context() {
+        use ::{Access, AccessContext, DangerousOption, ExceptionHandler, State};
+        use std::panic;
+
+        enum ContextHandler {}
+
+        impl ExceptionHandler for ContextHandler {
+            fn handle(context: &AccessContext) -> ! {
+                panic::panic_any(*context)
+            }
+        }
+
+        let mut val = DangerousOption::<i32, ContextHandler>::new_uninitialized();
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| *val = 42));
+        let context = *result.unwrap_err().downcast::<AccessContext>().unwrap();
+        assert_eq!(context.access(), Access::DerefMut);
+        assert_eq!(context.container(), "DangerousOption");
+        assert_eq!(context.type_name(), "i32");
+        assert_eq!(context.state(), State::Uninitialized);
+        assert!(context.label().is_none());
+    }
+
+    #[test]
+    fn zero_argument_callbacks() {
+        use ::{DangerousOption, ExceptionHandler};
+        use std::panic;
+
+        enum OldHandler {}
+
+        impl ExceptionHandler for OldHandler {
+            fn bad_deref() -> ! {
+                panic!("custom deref")
+            }
+
+            fn bad_take() -> ! {
+                panic!("custom take")
+            }
+        }
+
+        let mut val = DangerousOption::<i32, OldHandler>::new_uninitialized();
+        let result = panic::catch_unwind(|| *val);
+        assert_eq!(*result.unwrap_err().downcast::<&str>().unwrap(), "custom deref");
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| DangerousOption::take_unchecked(&mut val)));
+        assert_eq!(*result.unwrap_err().downcast::<&str>().unwrap(), "custom take");
+    }
+
+    #[test]
+    fn hook() {
+        use ::{set_hook, take_hook, AccessContext, DangerousOption};
+        use std::panic;
+
+        // The hook is global, so it must ignore other tests.
+        struct Hooked;
+
+        fn hook(context: &AccessContext) {
+            if context.type_name().ends_with("Hooked") {
+                panic::panic_any(context.location().line());
+            }
+        }
+
+        set_hook(hook);
+        let val = DangerousOption::<Hooked>::new_uninitialized();
+        let (result, line) = (panic::catch_unwind(|| { let _ = &*val; }), line!());
+        assert!(take_hook().is_some());
+        assert_eq!(*result.unwrap_err().downcast::<u32>().unwrap(), line);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dereferenced uninitialized DangerousOption<i32> at src/handler.rs:")]
+    fn default_message() {
+        use ::DangerousOption;
+
+        let val = DangerousOption::<i32>::new_uninitialized();
+        let _ = *val;
+    }
+
+    #[test]
+    #[cfg(feature = "track-state")]
+    #[should_panic(expected = "Dereferenced DangerousOption<i32> after its value was taken out at src/handler.rs:")]
+    fn taken_message() {
+        use ::DangerousOption;
+
+        let mut val = DangerousOption::<i32>::new(42);
+        DangerousOption::take_unchecked(&mut val);
+        let _ = *val;
+    }
+}
@@ -0,0 +1,108 @@
+//! Value which is initialized exactly once.
+
+use ::{Access, DangerousOption, DefaultExceptionHandler, ExceptionHandler, Inner};
+
+/// Represents a value that is initialized exactly once, some time after it's created.
+///
+/// Unlike `DangerousOption`, the value can't be replaced or taken out. Attempt to initialize the
+/// value again calls `ExceptionHandler::bad_reinit()`. Dereferencing uninitialized value calls
+/// the ExceptionHandler the same way `DangerousOption` does.
+pub struct LateInit<T, H: ExceptionHandler = DefaultExceptionHandler>(DangerousOption<T, H>);
+
+impl<T, H: ExceptionHandler> LateInit<T, H> {
+    /// Creates initialized value.
+    pub const fn new(val: T) -> Self {
+        LateInit(DangerousOption::new(val))
+    }
+
+    /// Creates uninitialized value.
+    #[track_caller]
+    pub const fn new_uninitialized() -> Self {
+        LateInit(DangerousOption::new_uninitialized())
+    }
+
+    /// Initializes the value, failing if it was already initialized.
+    #[track_caller]
+    pub fn init(this: &mut Self, val: T) {
+        if DangerousOption::is_initialized(&this.0) {
+            this.0.fail_as("LateInit", Access::Init);
+        }
+        DangerousOption::put(&mut this.0, val);
+    }
+
+    /// Returns `true` if the value is initialized.
+    pub fn is_initialized(this: &Self) -> bool {
+        DangerousOption::is_initialized(&this.0)
+    }
+
+    /// Non-panicking version of deref, which returns `None`, if value is uninitialized.
+    pub fn try(this: &Self) -> Option<&T> {
+        DangerousOption::try(&this.0)
+    }
+
+    /// Non-panicking version of deref_mut, which returns `None`, if value is uninitialized.
+    pub fn try_mut(this: &mut Self) -> Option<&mut T> {
+        DangerousOption::try_mut(&mut this.0)
+    }
+}
+
+impl<T, H: ExceptionHandler> core::ops::Deref for LateInit<T, H> {
+    type Target = T;
+
+    #[track_caller]
+    fn deref(&self) -> &Self::Target {
+        match self.0.inner {
+            Inner::Initialized(ref val) => val,
+            Inner::Uninitialized(_) | Inner::Taken(_) | Inner::Lost(_) | Inner::Poisoned(_) => self.0.fail_as("LateInit", Access::Deref),
+        }
+    }
+}
+
+impl<T, H: ExceptionHandler> core::ops::DerefMut for LateInit<T, H> {
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self.0.inner {
+            Inner::Initialized(ref mut val) => val,
+            Inner::Uninitialized(_) | Inner::Taken(_) | Inner::Lost(_) | Inner::Poisoned(_) => self.0.fail_as("LateInit", Access::DerefMut),
+        }
+    }
+}
+
+impl<T: core::fmt::Debug, H: ExceptionHandler> core::fmt::Debug for LateInit<T, H> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn init() {
+        use ::LateInit;
+
+        let mut val = LateInit::<i32>::new_uninitialized();
+        assert!(LateInit::try(&val).is_none());
+        LateInit::init(&mut val, 42);
+        assert!(LateInit::is_initialized(&val));
+        *val += 5;
+        assert_eq!(*val, 47);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempt to initialize already initialized LateInit<i32>")]
+    fn reinit() {
+        use ::LateInit;
+
+        let mut val = LateInit::<i32>::new(42);
+        LateInit::init(&mut val, 47);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dereferenced uninitialized LateInit<i32>")]
+    fn uninitialized() {
+        use ::LateInit;
+
+        let val = LateInit::<i32>::new_uninitialized();
+        let _ = *val;
+    }
+}
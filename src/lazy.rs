@@ -0,0 +1,160 @@
+//! Value initialized on first access.
+
+use core::cell::UnsafeCell;
+use core::panic::Location;
+use ::{handler, Access, AccessContext, DefaultExceptionHandler, ExceptionHandler, State};
+
+enum LazyState<T, F> {
+    Pending(F),
+    Running,
+    Ready(T),
+    Failed,
+}
+
+/// Represents a value which is initialized using stored initializer on first dereference.
+///
+/// If the initializer panics, the value becomes poisoned and all later accesses call
+/// `ExceptionHandler::bad_init_failed()`. Accessing the value from within its own initializer
+/// counts as accessing uninitialized value.
+///
+/// This type is not `Sync`. Use `DangerousOnce` to share late-initialized values between threads.
+pub struct DangerousLazy<T, F = fn() -> T, H: ExceptionHandler = DefaultExceptionHandler> {
+    state: UnsafeCell<LazyState<T, F>>,
+    _handler: core::marker::PhantomData<H>,
+}
+
+/// Marks the value as failed if the initializer unwinds.
+struct FailOnUnwind<'a, T: 'a, F: 'a>(&'a UnsafeCell<LazyState<T, F>>);
+
+impl<'a, T, F> Drop for FailOnUnwind<'a, T, F> {
+    fn drop(&mut self) {
+        // The initializer is no longer running, so nobody else accesses the state.
+        unsafe {
+            *self.0.get() = LazyState::Failed;
+        }
+    }
+}
+
+impl<T, F: FnOnce() -> T, H: ExceptionHandler> DangerousLazy<T, F, H> {
+    /// Creates the value which will be initialized using `init`.
+    pub const fn new(init: F) -> Self {
+        DangerousLazy { state: UnsafeCell::new(LazyState::Pending(init)), _handler: core::marker::PhantomData }
+    }
+
+    /// Initializes the value if it wasn't initialized yet and returns reference to it.
+    ///
+    /// This is the same as dereferencing.
+    #[track_caller]
+    pub fn force(this: &Self) -> &T {
+        // References into the state never outlive the match arms, so the initializer can access
+        // the state again.
+        let init = match unsafe { &*this.state.get() } {
+            LazyState::Ready(ref val) => return val,
+            LazyState::Pending(_) => match unsafe { core::ptr::replace(this.state.get(), LazyState::Running) } {
+                LazyState::Pending(init) => init,
+                _ => unreachable!(),
+            },
+            LazyState::Running => this.fail(Access::Deref, State::Uninitialized),
+            LazyState::Failed => this.fail(Access::Deref, State::InitFailed),
+        };
+
+        let guard = FailOnUnwind(&this.state);
+        let val = init();
+        core::mem::forget(guard);
+        unsafe {
+            *this.state.get() = LazyState::Ready(val);
+        }
+        match unsafe { &*this.state.get() } {
+            LazyState::Ready(ref val) => val,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the value if it was already initialized, without running the initializer.
+    pub fn try(this: &Self) -> Option<&T> {
+        match unsafe { &*this.state.get() } {
+            LazyState::Ready(ref val) => Some(val),
+            LazyState::Pending(_) | LazyState::Running | LazyState::Failed => None,
+        }
+    }
+
+    /// Returns `true` if the initializer panicked.
+    pub fn is_poisoned(this: &Self) -> bool {
+        match unsafe { &*this.state.get() } {
+            LazyState::Failed => true,
+            LazyState::Pending(_) | LazyState::Running | LazyState::Ready(_) => false,
+        }
+    }
+
+    #[track_caller]
+    fn fail(&self, access: Access, state: State) -> ! {
+        let context = AccessContext::new::<T>("DangerousLazy", access, Location::caller(), state, None);
+        handler::dispatch::<H>(&context)
+    }
+}
+
+impl<T, F: FnOnce() -> T, H: ExceptionHandler> core::ops::Deref for DangerousLazy<T, F, H> {
+    type Target = T;
+
+    #[track_caller]
+    fn deref(&self) -> &Self::Target {
+        DangerousLazy::force(self)
+    }
+}
+
+impl<T, F: FnOnce() -> T, H: ExceptionHandler> core::ops::DerefMut for DangerousLazy<T, F, H> {
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if let LazyState::Failed = *self.state.get_mut() {
+            self.fail(Access::DerefMut, State::InitFailed);
+        }
+        DangerousLazy::force(self);
+        match *self.state.get_mut() {
+            LazyState::Ready(ref mut val) => val,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<T: core::fmt::Debug, F: FnOnce() -> T, H: ExceptionHandler> core::fmt::Debug for DangerousLazy<T, F, H> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match unsafe { &*self.state.get() } {
+            LazyState::Ready(ref val) => core::fmt::Debug::fmt(val, f),
+            LazyState::Pending(_) | LazyState::Running => write!(f, "<uninitialized>"),
+            LazyState::Failed => write!(f, "<poisoned>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::DangerousLazy;
+
+    #[test]
+    fn lazy() {
+        use core::cell::Cell;
+
+        let calls = Cell::new(0);
+        let mut val = DangerousLazy::<i32, _>::new(|| {
+            calls.set(calls.get() + 1);
+            42
+        });
+        assert!(DangerousLazy::try(&val).is_none());
+        assert_eq!(*val, 42);
+        *val += 5;
+        assert_eq!(*val, 47);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn poisoned() {
+        use std::panic;
+
+        let val = DangerousLazy::<i32, _>::new(|| panic!("initializer failed"));
+        assert!(panic::catch_unwind(panic::AssertUnwindSafe(|| *val)).is_err());
+        assert!(DangerousLazy::is_poisoned(&val));
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| *val));
+        let message = result.unwrap_err().downcast::<std::string::String>().unwrap();
+        assert!(message.starts_with("Dereferenced DangerousLazy<i32> after its initializer panicked"));
+    }
+}
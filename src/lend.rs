@@ -0,0 +1,98 @@
+//! Temporarily taking the value out of `DangerousOption`.
+
+use core::mem::ManuallyDrop;
+use ::{DangerousOption, ExceptionHandler, Inner};
+
+/// Guard owning the value taken out of `DangerousOption` using `DangerousOption::lend()`.
+///
+/// The value is put back when the guard is dropped, even if it's dropped during unwinding or
+/// early return. Use `Lend::consume()` to keep the value and leave the `DangerousOption`
+/// uninitialized.
+pub struct Lend<'a, T: 'a, H: ExceptionHandler + 'a> {
+    option: &'a mut DangerousOption<T, H>,
+    value: ManuallyDrop<T>,
+}
+
+impl<'a, T, H: ExceptionHandler> Lend<'a, T, H> {
+    pub(crate) fn new(option: &'a mut DangerousOption<T, H>, value: T) -> Self {
+        Lend { option, value: ManuallyDrop::new(value) }
+    }
+
+    /// Takes the value, leaving the `DangerousOption` uninitialized the same way
+    /// `DangerousOption::take_unchecked()` at the place of `lend()` would.
+    pub fn consume(this: Self) -> T {
+        let mut this = ManuallyDrop::new(this);
+        // The guard is never dropped, so the value is not put back.
+        unsafe { ManuallyDrop::take(&mut this.value) }
+    }
+}
+
+impl<'a, T, H: ExceptionHandler> core::ops::Deref for Lend<'a, T, H> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<'a, T, H: ExceptionHandler> core::ops::DerefMut for Lend<'a, T, H> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<'a, T: core::fmt::Debug, H: ExceptionHandler> core::fmt::Debug for Lend<'a, T, H> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&*self.value, f)
+    }
+}
+
+impl<'a, T, H: ExceptionHandler> Drop for Lend<'a, T, H> {
+    fn drop(&mut self) {
+        // The value is never accessed again.
+        let value = unsafe { ManuallyDrop::take(&mut self.value) };
+        self.option.inner = Inner::Initialized(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::{DangerousOption, Lend};
+
+    #[test]
+    fn restore() {
+        use std::panic;
+
+        let mut val = DangerousOption::<i32>::new(42);
+        {
+            let mut lent = DangerousOption::lend(&mut val);
+            *lent += 5;
+        }
+        assert_eq!(*val, 47);
+
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            let mut lent = DangerousOption::lend(&mut val);
+            *lent = 42;
+            panic!("failed");
+        }));
+        assert!(result.is_err());
+        assert_eq!(*val, 42);
+    }
+
+    #[test]
+    fn consume() {
+        let mut val = DangerousOption::<i32>::new(42);
+        let lent = DangerousOption::lend(&mut val);
+        assert_eq!(Lend::consume(lent), 42);
+        assert!(!DangerousOption::is_initialized(&val));
+        #[cfg(feature = "track-state")]
+        assert_eq!(DangerousOption::state(&val), ::State::Taken);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempt to take value from uninitialized DangerousOption<i32>")]
+    fn uninitialized() {
+        let mut val = DangerousOption::<i32>::new_uninitialized();
+        DangerousOption::lend(&mut val);
+    }
+}
@@ -6,8 +6,16 @@
 //! While such thing might look like a step back (there's a reason we don't have NULL pointers in
 //! Rust), there is still one advantage over classic approach of NULL-pointer exceptions (including
 //! manually unwrapping): the cause of the bug is usually not in the place where dereferencing
-//! happened but in the place where assignment happened. Since this type has only three, very
-//! unique methods for creating invalid value, those can be easily searched for and tracked.
+//! happened but in the place where assignment happened. Since this type has only a few, very
+//! unique ways of creating invalid value, those can be easily searched for and tracked:
+//!
+//! * `new_uninitialized()` and `new_uninitialized_labeled()`
+//! * taking the value out using `take_unchecked()`, `take_checked()`, `take()`, `take_if()` or
+//!   `Lend::consume()`
+//! * converting `None` using `From`/`Into`
+//! * panicking in the function passed to `replace_with()` or, without the `track-state` feature,
+//!   `update()`
+//! * deserializing `null` or missing field (requires `serde` feature)
 //!
 //! It has also intentionally long name to prevent over-use. Also the methods creating dangerous
 //! state have longer names then those creating valid state.
@@ -18,31 +26,314 @@
 //!
 //! Finally, it also provides an exception handler which allows customizing panic message, logging,
 //! etc. There is a default handler which just panics, but in contexts where there is a more
-//! concrete, known cause of invalid operation, overriding the message is encouraged.
+//! concrete, known cause of invalid operation, overriding the message is encouraged. The
+//! `exception_handler!` macro makes declaring such handler easy. Logging or crash reporting can
+//! also be installed globally using `set_hook()`.
+//!
+//! For values which should be initialized exactly once, there's `LateInit`, which reports attempts
+//! to initialize it again to the exception handler, and its thread-safe version `DangerousOnce`
+//! usable in `static`s. `DangerousCell` can be initialized through shared reference and
+//! `DangerousLazy` initializes the value on first access instead. Finally, `DangerousUnchecked`
+//! checks the accesses in debug builds only, at the cost of `unsafe` contract, and
+//! `DangerousSentinel` uses a reserved value of the type to represent uninitialized state.
+//!
+//! With the `track-state` feature enabled, `DangerousOption` distinguishes values which were never
+//! initialized from values which were taken out, lost or poisoned, at the cost of possibly bigger
+//! size. The `track-uninitialized` feature additionally records the place where the value became
+//! uninitialized.
+//!
+//! With the `labels` feature enabled, values created using `new_labeled()` or
+//! `new_uninitialized_labeled()` carry their label, which is reported on invalid access.
+//!
+//! With the `serde` feature enabled, `DangerousOption<T>` is (de)serialized as `T`, see the
+//! `serialization` module for details.
 //!
-//! This crate is `no_std`.
+//! With the `std` feature enabled, `PayloadExceptionHandler` panics with typed
+//! `DangerousAccessError` payload, which can be recognized after catching the panic.
+//!
+//! With the `log` feature enabled, handlers declared using `exception_handler!` log the message
+//! before panicking.
+//!
+//! This crate is `no_std` unless the `std` feature is enabled.
 
 #![no_std]
 
-/// The exception handler defining behavior in case `None` is accessed.
-pub trait ExceptionHandler {
-    /// Called when dereferencing of `None` is attempted.
-    fn bad_deref() -> !;
+#[cfg(any(test, feature = "std"))]
+extern crate std;
+
+#[cfg(feature = "log")]
+extern crate log;
+
+#[cfg(feature = "serde")]
+extern crate serde;
+
+#[cfg(all(test, feature = "serde"))]
+#[macro_use]
+extern crate serde_derive;
+
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+
+#[cfg(all(test, feature = "serde"))]
+extern crate bincode;
+
+use core::panic::Location;
+
+#[macro_use]
+mod macros;
+
+mod cell;
+mod error;
+mod handler;
+mod late_init;
+mod lend;
+mod lazy;
+#[cfg(target_has_atomic = "8")]
+mod once;
+#[cfg(feature = "std")]
+mod payload;
+mod sentinel;
+mod unchecked;
+#[cfg(feature = "serde")]
+pub mod serialization;
+
+pub use cell::DangerousCell;
+pub use error::Uninitialized;
+pub use handler::{set_hook, take_hook, Access, AccessContext, DefaultExceptionHandler, ExceptionHandler};
+#[doc(hidden)]
+pub use handler::{handle_with_message as __handle_with_message, handle_with_messages as __handle_with_messages, Messages as __Messages};
+pub use late_init::LateInit;
+pub use lend::Lend;
+pub use lazy::DangerousLazy;
+#[cfg(target_has_atomic = "8")]
+pub use once::DangerousOnce;
+#[cfg(feature = "std")]
+pub use payload::{DangerousAccessError, PayloadExceptionHandler};
+pub use sentinel::{DangerousSentinel, MaxValue, NullPtr, Sentinel};
+pub use unchecked::DangerousUnchecked;
+
+/// The state in which a `DangerousOption` is.
+///
+/// Without the `track-state` feature, `DangerousOption` only distinguishes `Uninitialized` and
+/// `Initialized` - values which were taken out or lost are reported as `Uninitialized` and values
+/// are never poisoned.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum State {
+    /// The value was never initialized - it was created using `new_uninitialized()`.
+    Uninitialized,
+    /// The value is initialized.
+    Initialized,
+    /// The value was initialized, but it was taken out using `take_unchecked()` or
+    /// `take_checked()`.
+    Taken,
+    /// The initializer of `DangerousLazy` panicked, so the value will never be initialized.
+    InitFailed,
+    /// The value was lost because the function passed to `DangerousOption::replace_with()`
+    /// panicked.
+    Lost,
+    /// The value is present, but it may be inconsistent, because the function passed to
+    /// `DangerousOption::update()` panicked.
+    Poisoned,
+}
+
+/// Describes how a `DangerousOption` became uninitialized.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Cause {
+    /// The value was created uninitialized using `new_uninitialized()`.
+    Created,
+    /// The value was taken out using `take_unchecked()` or `take_checked()`.
+    Taken,
+    /// The value was lost because the function passed to `replace_with()` panicked.
+    Lost,
+}
+
+/// Records the place where a `DangerousOption` became uninitialized.
+///
+/// It's only recorded if the `track-uninitialized` feature is enabled.
+#[derive(Debug, Copy, Clone)]
+pub struct Provenance {
+    location: &'static Location<'static>,
+    cause: Cause,
+}
+
+impl Provenance {
+    /// The place in the code which made the value uninitialized.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// The operation which made the value uninitialized.
+    pub fn cause(&self) -> Cause {
+        self.cause
+    }
+}
+
+impl core::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self.cause {
+            Cause::Created => write!(f, "{} (created)", self.location),
+            Cause::Taken => write!(f, "{} (taken)", self.location),
+            Cause::Lost => write!(f, "{} (lost)", self.location),
+        }
+    }
+}
+
+/// The place where the value became uninitialized, if it's tracked.
+#[cfg(feature = "track-uninitialized")]
+#[derive(Copy, Clone)]
+struct Trace(&'static Location<'static>);
+
+#[cfg(feature = "track-uninitialized")]
+impl Trace {
+    #[track_caller]
+    const fn here() -> Self {
+        Trace(Location::caller())
+    }
+
+    fn location(self) -> Option<&'static Location<'static>> {
+        Some(self.0)
+    }
+}
+
+/// The place where the value became uninitialized, if it's tracked.
+#[cfg(not(feature = "track-uninitialized"))]
+#[derive(Copy, Clone)]
+struct Trace;
 
-    /// Called on attempt to take out value from `Some`, if there is `None`.
-    fn bad_take() -> !;
+#[cfg(not(feature = "track-uninitialized"))]
+impl Trace {
+    const fn here() -> Self {
+        Trace
+    }
+
+    fn location(self) -> Option<&'static Location<'static>> {
+        None
+    }
+}
+
+/// The label of the value, if labels are enabled.
+#[cfg(feature = "labels")]
+#[derive(Copy, Clone)]
+struct Label(Option<&'static str>);
+
+#[cfg(feature = "labels")]
+impl Label {
+    const NONE: Label = Label(None);
+
+    const fn new(label: &'static str) -> Self {
+        Label(Some(label))
+    }
+
+    fn get(self) -> Option<&'static str> {
+        self.0
+    }
+}
+
+/// The label of the value, if labels are enabled.
+#[cfg(not(feature = "labels"))]
+#[derive(Copy, Clone)]
+struct Label;
+
+#[cfg(not(feature = "labels"))]
+impl Label {
+    const NONE: Label = Label;
+
+    const fn new(_label: &'static str) -> Self {
+        Label
+    }
+
+    fn get(self) -> Option<&'static str> {
+        None
+    }
+}
+
+/// Payload of the states which are only distinguished if the `track-state` feature is enabled.
+#[cfg(feature = "track-state")]
+#[derive(Copy, Clone)]
+struct Tracked<T>(T);
+
+#[cfg(feature = "track-state")]
+impl<T> Tracked<T> {
+    fn into_inner(self) -> T {
+        self.0
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+#[derive(Copy, Clone)]
+#[cfg(not(feature = "track-state"))]
+enum Never {}
+
+/// Payload of the states which are only distinguished if the `track-state` feature is enabled.
+///
+/// Without the feature it's uninhabited, so the compiler leaves the states out of the layout and
+/// `Inner<T>` is as small as `Option<T>`.
+#[cfg(not(feature = "track-state"))]
+#[derive(Copy, Clone)]
+#[allow(dead_code)]
+enum Tracked<T> {
+    Never(Never, core::marker::PhantomData<T>),
+}
+
+#[cfg(not(feature = "track-state"))]
+impl<T> Tracked<T> {
+    fn into_inner(self) -> T {
+        match self {
+            Tracked::Never(never, _) => match never {},
+        }
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        match *self {
+            Tracked::Never(never, _) => match never {},
+        }
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(not(feature = "track-state"), allow(dead_code))]
+enum Inner<T> {
+    Uninitialized(Trace),
+    Initialized(T),
+    Taken(Tracked<Trace>),
+    Lost(Tracked<Trace>),
+    Poisoned(Tracked<T>),
 }
 
-/// This is the default handler for `None` exceptions.
-pub enum DefaultExceptionHandler {}
+impl<T> Inner<T> {
+    #[cfg(feature = "track-state")]
+    fn taken(trace: Trace) -> Self {
+        Inner::Taken(Tracked(trace))
+    }
+
+    #[cfg(not(feature = "track-state"))]
+    fn taken(trace: Trace) -> Self {
+        Inner::Uninitialized(trace)
+    }
+
+    #[cfg(feature = "track-state")]
+    fn lost(trace: Trace) -> Self {
+        Inner::Lost(Tracked(trace))
+    }
+
+    #[cfg(not(feature = "track-state"))]
+    fn lost(trace: Trace) -> Self {
+        Inner::Uninitialized(trace)
+    }
 
-impl ExceptionHandler for DefaultExceptionHandler {
-    fn bad_deref() -> ! {
-        panic!("Dereferenced uninitialized DangerousOption")
+    #[cfg(feature = "track-state")]
+    fn poisoned(val: T) -> Self {
+        Inner::Poisoned(Tracked(val))
     }
 
-    fn bad_take() -> ! {
-        panic!("Attempt to take value from uninitialized DangerousOption")
+    #[cfg(not(feature = "track-state"))]
+    fn poisoned(val: T) -> Self {
+        Inner::Initialized(val)
     }
 }
 
@@ -51,68 +342,539 @@ impl ExceptionHandler for DefaultExceptionHandler {
 ///
 /// When deref of initialized value is attempted, the ExceptionHandler is called. This will lead to
 /// aborting of the task.
-#[derive(Debug)]
-pub struct DangerousOption<T, H: ExceptionHandler = DefaultExceptionHandler>(Option<T>, core::marker::PhantomData<H>);
+///
+/// If the `track-state` feature is enabled, the value distinguishes between being never initialized
+/// and having its value taken out, see `State`. If the `track-uninitialized` feature is enabled,
+/// it also remembers where it became uninitialized and passes this information to the
+/// ExceptionHandler.
+///
+/// The `Debug` implementation prints the value transparently if it's initialized or
+/// `<uninitialized>`/`<taken>` marker otherwise. It never calls the ExceptionHandler.
+///
+/// If the `labels` feature is enabled, the value can have a label, which identifies it in the
+/// ExceptionHandler context, errors and `Debug` output. Without the feature, the labels are
+/// discarded, so the size of the value doesn't change.
+///
+/// Comparison and hashing never call the ExceptionHandler either; they behave the same as for
+/// `Option<T>`. All uninitialized values are equal to each other, regardless of whether they
+/// were taken out or never initialized and of where they became uninitialized, and they are
+/// less than any initialized value. Poisoned values count as uninitialized.
+///
+/// # Layout
+///
+/// The layout of `DangerousOption<T>` is unspecified. With the default features it has the same
+/// size as `Option<T>`, so types with a niche, such as references, `Box` or `NonZeroU32`, don't
+/// get bigger. The features make it bigger:
+///
+/// * `track-state` adds states which `Option<T>` can't represent, so the niche of `T` may not be
+///   enough and the value may grow by a discriminant. E.g. `DangerousOption<Box<T>>` has twice
+///   the size of a pointer.
+/// * `track-uninitialized` adds a pointer to the place where the value became uninitialized.
+/// * `labels` adds the label, which is a pointer and a length.
+///
+/// Even with the default features, the layout isn't guaranteed to be the same as the layout of
+/// `Option<T>`, so slices of `Option<T>` can't be viewed as slices of `DangerousOption<T>`.
+///
+/// If the size matters more than the diagnostics, consider `DangerousSentinel`, which has the
+/// size of `T`.
+pub struct DangerousOption<T, H: ExceptionHandler = DefaultExceptionHandler> {
+    inner: Inner<T>,
+    label: Label,
+    _handler: core::marker::PhantomData<H>,
+}
 
 impl<T, H: ExceptionHandler> core::ops::Deref for DangerousOption<T, H> {
     type Target = T;
 
+    #[track_caller]
     fn deref(&self) -> &Self::Target {
-        self.0.as_ref().unwrap_or_else(|| H::bad_deref())
+        match self.inner {
+            Inner::Initialized(ref val) => val,
+            Inner::Uninitialized(_) | Inner::Taken(_) | Inner::Lost(_) | Inner::Poisoned(_) => self.fail(Access::Deref),
+        }
     }
 }
 
 impl<T, H: ExceptionHandler> core::ops::DerefMut for DangerousOption<T, H> {
+    #[track_caller]
     fn deref_mut(&mut self) -> &mut Self::Target {
-        self.0.as_mut().unwrap_or_else(|| H::bad_deref())
+        match self.inner {
+            Inner::Initialized(ref mut val) => val,
+            Inner::Uninitialized(_) | Inner::Taken(_) | Inner::Lost(_) | Inner::Poisoned(_) => self.fail(Access::DerefMut),
+        }
     }
 }
 
 impl<T, H: ExceptionHandler> DangerousOption<T, H> {
+    /// Reports invalid access to the handler.
+    #[track_caller]
+    fn fail(&self, access: Access) -> ! {
+        self.fail_as("DangerousOption", access)
+    }
+
+    /// Reports invalid access to the handler on behalf of a type wrapping `DangerousOption`.
+    #[track_caller]
+    fn fail_as(&self, container: &'static str, access: Access) -> ! {
+        let context = AccessContext::new::<T>(container, access, Location::caller(), DangerousOption::state(self), DangerousOption::uninitialized_at(self))
+            .with_label(DangerousOption::label(self));
+        handler::dispatch::<H>(&context)
+    }
+
+    /// Takes out the value, including poisoned one.
+    #[track_caller]
+    fn take_value(&mut self) -> Option<T> {
+        match self.inner {
+            Inner::Initialized(_) | Inner::Poisoned(_) => match core::mem::replace(&mut self.inner, Inner::taken(Trace::here())) {
+                Inner::Initialized(val) => Some(val),
+                Inner::Poisoned(val) => Some(val.into_inner()),
+                _ => unreachable!(),
+            },
+            Inner::Uninitialized(_) | Inner::Taken(_) | Inner::Lost(_) => None,
+        }
+    }
+
+    /// Returns mutable reference to the value, including poisoned one.
+    fn value_mut(&mut self) -> Option<&mut T> {
+        match self.inner {
+            Inner::Initialized(ref mut val) => Some(val),
+            Inner::Poisoned(ref mut val) => Some(val.get_mut()),
+            Inner::Uninitialized(_) | Inner::Taken(_) | Inner::Lost(_) => None,
+        }
+    }
+
     /// Creates valid value.
-    pub fn new(val: T) -> Self {
-        DangerousOption(Some(val), Default::default())
+    pub const fn new(val: T) -> Self {
+        DangerousOption { inner: Inner::Initialized(val), label: Label::NONE, _handler: core::marker::PhantomData }
+    }
+
+    /// Creates valid value with a label identifying it in diagnostics.
+    ///
+    /// The label is only stored if the `labels` feature is enabled.
+    pub const fn new_labeled(val: T, label: &'static str) -> Self {
+        DangerousOption { inner: Inner::Initialized(val), label: Label::new(label), _handler: core::marker::PhantomData }
     }
 
     /// Creates uninitialized value.
-    pub fn new_uninitialized() -> Self {
-        DangerousOption(None, Default::default())
+    ///
+    /// This is a `const fn`, so it can be used to initialize `static`s and `const`s. However,
+    /// since a `static` can't be mutated safely, consider using `DangerousOnce` in such cases.
+    #[track_caller]
+    pub const fn new_uninitialized() -> Self {
+        DangerousOption { inner: Inner::Uninitialized(Trace::here()), label: Label::NONE, _handler: core::marker::PhantomData }
+    }
+
+    /// Creates uninitialized value with a label identifying it in diagnostics.
+    ///
+    /// The label is only stored if the `labels` feature is enabled.
+    #[track_caller]
+    pub const fn new_uninitialized_labeled(label: &'static str) -> Self {
+        DangerousOption { inner: Inner::Uninitialized(Trace::here()), label: Label::new(label), _handler: core::marker::PhantomData }
+    }
+
+    /// Returns the label of the value.
+    ///
+    /// Returns `None` if the value has no label or if the `labels` feature is disabled.
+    pub fn label(this: &Self) -> Option<&'static str> {
+        this.label.get()
     }
 
     /// Takes out the value, failing if it's not there. After call to this function, the value is
     /// uninitialized.
+    #[track_caller]
     pub fn take_unchecked(this: &mut Self) -> T {
-        this.0.take().unwrap_or_else(|| H::bad_take())
+        match this.take_value() {
+            Some(val) => val,
+            None => this.fail(Access::Take),
+        }
+    }
+
+    /// Replaces the value with the result of `f` called with the current value, failing if it's
+    /// not there.
+    ///
+    /// If `f` panics, the value is lost and all accesses fail until a new value is put in. With the
+    /// `track-state` feature, they call `ExceptionHandler::bad_lost()`.
+    #[track_caller]
+    pub fn replace_with<F: FnOnce(T) -> T>(this: &mut Self, f: F) {
+        let val = match core::mem::replace(&mut this.inner, Inner::lost(Trace::here())) {
+            Inner::Initialized(val) => val,
+            inner => {
+                this.inner = inner;
+                this.fail(Access::DerefMut);
+            },
+        };
+        this.inner = Inner::Initialized(f(val));
+    }
+
+    /// Calls `f` with mutable reference to the value, failing if it's not there.
+    ///
+    /// If `f` panics, the value becomes poisoned, because it may be left inconsistent. Accessing
+    /// poisoned value calls `ExceptionHandler::bad_poisoned()`. The poisoning can be cleared using
+    /// `clear_poison()` or by putting a new value in. Mutable access via `DerefMut` never poisons
+    /// the value, so using this function is the way to opt in.
+    ///
+    /// Unlike `Mutex` poisoning, this works in `no_std`, since it doesn't need to detect
+    /// unwinding - if `f` returns, the poisoning is cleared.
+    ///
+    /// Poisoning only prevents borrowing the value - `Deref`, `try()`, `get()`, `update()`,
+    /// `get_or_insert_with()` and others fail and comparisons treat the value as uninitialized.
+    /// Functions which take the value out or consume the `DangerousOption` return poisoned value
+    /// like initialized one, so it can always be recovered, similarly to
+    /// `PoisonError::into_inner()`. These are `take_unchecked()`, `take_checked()`, `take()`,
+    /// `take_if()`, `lend()`, `put()`, `replace()`, `into_option()`, `filter()`, `zip()` and
+    /// `ok_or()`. `map()` maps poisoned value, keeping it poisoned.
+    ///
+    /// Poisoning requires the `track-state` feature. Without it, the value is moved out while `f`
+    /// runs, so if `f` panics, the value is dropped and the `DangerousOption` stays uninitialized.
+    #[track_caller]
+    pub fn update<R, F: FnOnce(&mut T) -> R>(this: &mut Self, f: F) -> R {
+        this.update_value(f)
+    }
+
+    #[cfg(feature = "track-state")]
+    #[track_caller]
+    fn update_value<R, F: FnOnce(&mut T) -> R>(&mut self, f: F) -> R {
+        if !DangerousOption::is_initialized(self) {
+            self.fail(Access::DerefMut);
+        }
+        self.set_poisoned(true);
+        let result = match self.inner {
+            Inner::Poisoned(ref mut val) => f(val.get_mut()),
+            _ => unreachable!(),
+        };
+        self.set_poisoned(false);
+        result
+    }
+
+    #[cfg(not(feature = "track-state"))]
+    #[track_caller]
+    fn update_value<R, F: FnOnce(&mut T) -> R>(&mut self, f: F) -> R {
+        let mut val = match core::mem::replace(&mut self.inner, Inner::lost(Trace::here())) {
+            Inner::Initialized(val) => val,
+            inner => {
+                self.inner = inner;
+                self.fail(Access::DerefMut);
+            },
+        };
+        let result = f(&mut val);
+        self.inner = Inner::Initialized(val);
+        result
+    }
+
+    /// Returns `true` if the value is poisoned.
+    pub fn is_poisoned(this: &Self) -> bool {
+        DangerousOption::state(this) == State::Poisoned
+    }
+
+    /// Clears the poisoning of the value, returning `true` if it was poisoned.
+    pub fn clear_poison(this: &mut Self) -> bool {
+        match core::mem::replace(&mut this.inner, Inner::Uninitialized(Trace::here())) {
+            Inner::Poisoned(val) => {
+                this.inner = Inner::Initialized(val.into_inner());
+                true
+            },
+            inner => {
+                this.inner = inner;
+                false
+            },
+        }
+    }
+
+    /// Moves the value between initialized and poisoned state. The value must be present.
+    #[cfg(feature = "track-state")]
+    #[track_caller]
+    fn set_poisoned(&mut self, poisoned: bool) {
+        let val = match core::mem::replace(&mut self.inner, Inner::lost(Trace::here())) {
+            Inner::Initialized(val) => val,
+            Inner::Poisoned(val) => val.into_inner(),
+            _ => unreachable!(),
+        };
+        self.inner = if poisoned { Inner::poisoned(val) } else { Inner::Initialized(val) };
+    }
+
+    /// Takes out the value temporarily, failing if it's not there. The returned guard puts the
+    /// value back when dropped, so the value stays initialized even if a panic or early return
+    /// happens while it's taken out.
+    #[track_caller]
+    pub fn lend<'a>(this: &'a mut Self) -> Lend<'a, T, H> {
+        let val = DangerousOption::take_unchecked(this);
+        Lend::new(this, val)
     }
 
     /// Tries to take out the value. After call to this function, the value is uninitialized.
+    #[track_caller]
     pub fn take_checked(this: &mut Self) -> Option<T> {
-        this.0.take()
+        this.take_value()
     }
 
-    /// Non-panicking version of deref, which returns `None`, if value is uninitiaized.
+    /// Non-panicking version of deref, which returns `None`, if value is uninitialized.
     pub fn try(this: &Self) -> Option<&T> {
-        this.0.as_ref()
+        match this.inner {
+            Inner::Initialized(ref val) => Some(val),
+            Inner::Uninitialized(_) | Inner::Taken(_) | Inner::Lost(_) | Inner::Poisoned(_) => None,
+        }
     }
 
-    /// Non-panicking version of deref_mut, which returns `None`, if value is uninitiaized.
+    /// Non-panicking version of deref_mut, which returns `None`, if value is uninitialized.
     pub fn try_mut(this: &mut Self) -> Option<&mut T> {
-        this.0.as_mut()
+        match this.inner {
+            Inner::Initialized(ref mut val) => Some(val),
+            Inner::Uninitialized(_) | Inner::Taken(_) | Inner::Lost(_) | Inner::Poisoned(_) => None,
+        }
+    }
+
+    /// Returns the value or an error describing why it's uninitialized.
+    pub fn get(this: &Self) -> Result<&T, Uninitialized> {
+        match this.inner {
+            Inner::Initialized(ref val) => Ok(val),
+            Inner::Uninitialized(_) | Inner::Taken(_) | Inner::Lost(_) | Inner::Poisoned(_) => Err(this.error()),
+        }
+    }
+
+    /// Returns mutable reference to the value or an error describing why it's uninitialized.
+    pub fn get_mut(this: &mut Self) -> Result<&mut T, Uninitialized> {
+        match this.inner {
+            Inner::Initialized(ref mut val) => Ok(val),
+            Inner::Uninitialized(_) | Inner::Taken(_) | Inner::Lost(_) | Inner::Poisoned(_) => Err(this.error()),
+        }
+    }
+
+    /// Takes out the value or returns an error describing why it's uninitialized. After successful
+    /// call to this function, the value is uninitialized.
+    #[track_caller]
+    pub fn take(this: &mut Self) -> Result<T, Uninitialized> {
+        match this.take_value() {
+            Some(val) => Ok(val),
+            None => Err(this.error()),
+        }
+    }
+
+    fn error(&self) -> Uninitialized {
+        Uninitialized::new::<T>(DangerousOption::state(self), DangerousOption::label(self), DangerousOption::uninitialized_at(self))
     }
 
-    /// Puts the new value in place of old, optionally returning old value.
+    /// Puts the new value in place of old, optionally returning old value. If the old value was
+    /// poisoned, it's returned too and the poisoning is cleared.
     pub fn put(this: &mut Self, val: T) -> Option<T> {
-        core::mem::replace(&mut this.0, Some(val))
+        match core::mem::replace(&mut this.inner, Inner::Initialized(val)) {
+            Inner::Initialized(old) => Some(old),
+            Inner::Poisoned(old) => Some(old.into_inner()),
+            Inner::Uninitialized(_) | Inner::Taken(_) | Inner::Lost(_) => None,
+        }
+    }
+
+    /// Returns the state of the value.
+    pub fn state(this: &Self) -> State {
+        match this.inner {
+            Inner::Uninitialized(_) => State::Uninitialized,
+            Inner::Initialized(_) => State::Initialized,
+            Inner::Taken(_) => State::Taken,
+            Inner::Lost(_) => State::Lost,
+            Inner::Poisoned(_) => State::Poisoned,
+        }
+    }
+
+    /// Returns the place where the value became uninitialized.
+    ///
+    /// Returns `None` if the value is initialized or if the `track-uninitialized` feature is
+    /// disabled.
+    pub fn uninitialized_at(this: &Self) -> Option<Provenance> {
+        let (trace, cause) = match this.inner {
+            Inner::Uninitialized(trace) => (trace, Cause::Created),
+            Inner::Taken(trace) => (trace.into_inner(), Cause::Taken),
+            Inner::Lost(trace) => (trace.into_inner(), Cause::Lost),
+            Inner::Initialized(_) | Inner::Poisoned(_) => return None,
+        };
+
+        trace.location().map(|location| Provenance { location, cause })
+    }
+
+    /// Converts the value into `Option`, returning `None` if it's uninitialized.
+    pub fn into_option(this: Self) -> Option<T> {
+        match this.inner {
+            Inner::Initialized(val) => Some(val),
+            Inner::Poisoned(val) => Some(val.into_inner()),
+            Inner::Uninitialized(_) | Inner::Taken(_) | Inner::Lost(_) => None,
+        }
+    }
+
+    /// Returns `Option` view of the value. This is the same as `try()`, named to match
+    /// `into_option()`.
+    pub fn as_option(this: &Self) -> Option<&T> {
+        DangerousOption::try(this)
+    }
+
+    /// Returns mutable `Option` view of the value. This is the same as `try_mut()`, named to match
+    /// `into_option()`.
+    pub fn as_option_mut(this: &mut Self) -> Option<&mut T> {
+        DangerousOption::try_mut(this)
+    }
+
+    /// Changes the ExceptionHandler, keeping the state of the value, including its provenance.
+    pub fn with_handler<H2: ExceptionHandler>(this: Self) -> DangerousOption<T, H2> {
+        DangerousOption { inner: this.inner, label: this.label, _handler: Default::default() }
+    }
+
+    /// Returns `true` if the value is initialized.
+    pub fn is_initialized(this: &Self) -> bool {
+        match this.inner {
+            Inner::Initialized(_) => true,
+            Inner::Uninitialized(_) | Inner::Taken(_) | Inner::Lost(_) | Inner::Poisoned(_) => false,
+        }
+    }
+
+    /// Maps the value using provided function. If the value is uninitialized, the result is
+    /// uninitialized too, keeping the original state and provenance.
+    pub fn map<U, F: FnOnce(T) -> U>(this: Self, f: F) -> DangerousOption<U, H> {
+        let inner = match this.inner {
+            Inner::Initialized(val) => Inner::Initialized(f(val)),
+            Inner::Uninitialized(trace) => Inner::Uninitialized(trace),
+            Inner::Taken(trace) => Inner::Taken(trace),
+            Inner::Lost(trace) => Inner::Lost(trace),
+            Inner::Poisoned(val) => Inner::poisoned(f(val.into_inner())),
+        };
+        DangerousOption { inner, label: this.label, _handler: Default::default() }
+    }
+
+    /// Dereferences the value (if initialized) using `Deref` of `T`.
+    pub fn as_deref(this: &Self) -> Option<&T::Target> where T: core::ops::Deref {
+        DangerousOption::try(this).map(|val| &**val)
+    }
+
+    /// Returns the value, initializing it using provided function first if it's uninitialized.
+    ///
+    /// Poisoned value is neither returned nor replaced, this fails instead.
+    #[track_caller]
+    pub fn get_or_insert_with<F: FnOnce() -> T>(this: &mut Self, f: F) -> &mut T {
+        if DangerousOption::is_poisoned(this) {
+            this.fail(Access::DerefMut);
+        }
+        if !DangerousOption::is_initialized(this) {
+            this.inner = Inner::Initialized(f());
+        }
+
+        match this.inner {
+            Inner::Initialized(ref mut val) => val,
+            Inner::Uninitialized(_) | Inner::Taken(_) | Inner::Lost(_) | Inner::Poisoned(_) => unreachable!(),
+        }
+    }
+
+    /// Puts the new value in place of old, optionally returning old value. This is the same as
+    /// `put()`, named to match `Option::replace()`.
+    pub fn replace(this: &mut Self, val: T) -> Option<T> {
+        DangerousOption::put(this, val)
+    }
+
+    /// Takes out the value if it's initialized and the predicate returns `true`. After taking the
+    /// value out, it's uninitialized.
+    #[track_caller]
+    pub fn take_if<P: FnOnce(&mut T) -> bool>(this: &mut Self, predicate: P) -> Option<T> {
+        match this.value_mut().map(predicate) {
+            Some(true) => this.take_value(),
+            Some(false) | None => None,
+        }
+    }
+
+    /// Returns the value if it's initialized and the predicate returns `true`.
+    ///
+    /// Unlike `Option::filter()` this returns `Option` in order to not create uninitialized value
+    /// implicitly. The same applies to `zip()`.
+    pub fn filter<P: FnOnce(&T) -> bool>(this: Self, predicate: P) -> Option<T> {
+        DangerousOption::into_option(this).filter(predicate)
+    }
+
+    /// Returns both values if both are initialized.
+    pub fn zip<U>(this: Self, other: DangerousOption<U, H>) -> Option<(T, U)> {
+        DangerousOption::into_option(this).zip(DangerousOption::into_option(other))
+    }
+
+    /// Returns the value if it's initialized or `err` otherwise.
+    pub fn ok_or<E>(this: Self, err: E) -> Result<T, E> {
+        DangerousOption::into_option(this).ok_or(err)
+    }
+}
+
+impl<T, H: ExceptionHandler> From<T> for DangerousOption<T, H> {
+    fn from(val: T) -> Self {
+        DangerousOption::new(val)
+    }
+}
+
+/// Converts `None` into uninitialized value.
+///
+/// Note that `None.into()` can't infer its type if `T` is itself an `Option`, since both this
+/// impl and `From<T>` apply. Use `DangerousOption::new(None)` or
+/// `DangerousOption::new_uninitialized()` to say which one is meant.
+impl<T, H: ExceptionHandler> From<Option<T>> for DangerousOption<T, H> {
+    #[track_caller]
+    fn from(val: Option<T>) -> Self {
+        match val {
+            Some(val) => DangerousOption::new(val),
+            None => DangerousOption::new_uninitialized(),
+        }
+    }
+}
+
+impl<T, H: ExceptionHandler> From<DangerousOption<T, H>> for Option<T> {
+    fn from(val: DangerousOption<T, H>) -> Self {
+        DangerousOption::into_option(val)
+    }
+}
+
+impl<T: core::fmt::Debug, H: ExceptionHandler> core::fmt::Debug for DangerousOption<T, H> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let marker = match self.inner {
+            Inner::Initialized(ref val) => return core::fmt::Debug::fmt(val, f),
+            Inner::Uninitialized(_) => "uninitialized",
+            Inner::Taken(_) => "taken",
+            Inner::Lost(_) => "lost",
+            Inner::Poisoned(_) => "poisoned",
+        };
+
+        write!(f, "<{}", marker)?;
+        if let Some(label) = DangerousOption::label(self) {
+            write!(f, " `{}`", label)?;
+        }
+        if let Some(provenance) = DangerousOption::uninitialized_at(self) {
+            write!(f, " at {}", provenance.location())?;
+        }
+        write!(f, ">")
     }
 }
 
-impl<T> core::clone::Clone for DangerousOption<T> where T : Clone {
+impl<T, H: ExceptionHandler> core::clone::Clone for DangerousOption<T, H> where T : Clone {
     fn clone(&self) -> Self {
-        DangerousOption(self.0.clone(), Default::default())
+        DangerousOption { inner: self.inner.clone(), label: self.label, _handler: Default::default() }
+    }
+}
+
+impl<T: PartialEq, H: ExceptionHandler> PartialEq for DangerousOption<T, H> {
+    fn eq(&self, other: &Self) -> bool {
+        DangerousOption::try(self) == DangerousOption::try(other)
+    }
+}
+
+impl<T: Eq, H: ExceptionHandler> Eq for DangerousOption<T, H> {}
+
+impl<T: PartialOrd, H: ExceptionHandler> PartialOrd for DangerousOption<T, H> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        DangerousOption::try(self).partial_cmp(&DangerousOption::try(other))
+    }
+}
+
+impl<T: Ord, H: ExceptionHandler> Ord for DangerousOption<T, H> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        DangerousOption::try(self).cmp(&DangerousOption::try(other))
+    }
+}
+
+impl<T: core::hash::Hash, H: ExceptionHandler> core::hash::Hash for DangerousOption<T, H> {
+    fn hash<S: core::hash::Hasher>(&self, state: &mut S) {
+        DangerousOption::try(self).hash(state)
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::toplevel_ref_arg, dropping_copy_types)]
 mod tests {
     #[test]
     fn success() {
@@ -170,4 +932,346 @@ mod tests {
         let ref mut val2 = *val;
         *val2 = 42;
     }
+
+    #[test]
+    fn caller_location() {
+        use ::{AccessContext, DangerousOption, ExceptionHandler};
+        use std::panic;
+
+        enum LocationHandler {}
+
+        impl ExceptionHandler for LocationHandler {
+            fn handle(context: &AccessContext) -> ! {
+                panic::panic_any(context.location().line())
+            }
+        }
+
+        let mut val = DangerousOption::<i32, LocationHandler>::new_uninitialized();
+        let (result, line) = (panic::catch_unwind(|| *val), line!());
+        assert_eq!(*result.unwrap_err().downcast::<u32>().unwrap(), line);
+        let (result, line) = (panic::catch_unwind(panic::AssertUnwindSafe(|| DangerousOption::take_unchecked(&mut val))), line!());
+        assert_eq!(*result.unwrap_err().downcast::<u32>().unwrap(), line);
+    }
+
+    #[test]
+    #[cfg(feature = "track-uninitialized")]
+    fn provenance() {
+        use ::{Cause, DangerousOption};
+
+        let (mut val, line) = (DangerousOption::<i32>::new_uninitialized(), line!());
+        let provenance = DangerousOption::uninitialized_at(&val).unwrap();
+        assert_eq!(provenance.cause(), Cause::Created);
+        assert_eq!(provenance.location().line(), line);
+
+        DangerousOption::put(&mut val, 42);
+        assert!(DangerousOption::uninitialized_at(&val).is_none());
+
+        let (taken, line) = (DangerousOption::take_checked(&mut val), line!());
+        assert_eq!(taken, Some(42));
+        let provenance = DangerousOption::uninitialized_at(&val).unwrap();
+        assert_eq!(provenance.cause(), Cause::Taken);
+        assert_eq!(provenance.location().line(), line);
+    }
+
+    #[test]
+    #[cfg(feature = "track-state")]
+    fn state() {
+        use ::{AccessContext, DangerousOption, ExceptionHandler, State};
+        use std::panic;
+
+        enum StateHandler {}
+
+        impl ExceptionHandler for StateHandler {
+            fn handle(_context: &AccessContext) -> ! {
+                panic::panic_any(State::Uninitialized)
+            }
+
+            fn bad_deref_taken(_context: &AccessContext) -> ! {
+                panic::panic_any(State::Taken)
+            }
+        }
+
+        let mut val = DangerousOption::<i32, StateHandler>::new_uninitialized();
+        assert_eq!(DangerousOption::state(&val), State::Uninitialized);
+        let result = panic::catch_unwind(|| *val);
+        assert_eq!(*result.unwrap_err().downcast::<State>().unwrap(), State::Uninitialized);
+
+        DangerousOption::put(&mut val, 42);
+        assert_eq!(DangerousOption::state(&val), State::Initialized);
+        DangerousOption::take_unchecked(&mut val);
+        assert_eq!(DangerousOption::state(&val), State::Taken);
+        let result = panic::catch_unwind(|| *val);
+        assert_eq!(*result.unwrap_err().downcast::<State>().unwrap(), State::Taken);
+        // bad_take_taken falls back to handle
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| DangerousOption::take_unchecked(&mut val)));
+        assert_eq!(*result.unwrap_err().downcast::<State>().unwrap(), State::Uninitialized);
+    }
+
+    #[test]
+    fn debug() {
+        use ::{DangerousOption, ExceptionHandler};
+        use std::format;
+
+        enum NotDebug {}
+
+        impl ExceptionHandler for NotDebug {}
+
+        let mut val = DangerousOption::<i32, NotDebug>::new(42);
+        assert_eq!(format!("{:?}", val), "42");
+        DangerousOption::take_unchecked(&mut val);
+        #[cfg(not(feature = "track-state"))]
+        assert_eq!(format!("{:?}", val), "<uninitialized>");
+        #[cfg(all(feature = "track-state", not(feature = "track-uninitialized")))]
+        assert_eq!(format!("{:?}", val), "<taken>");
+        #[cfg(feature = "track-uninitialized")]
+        assert!(format!("{:?}", val).starts_with("<taken at src/lib.rs:"));
+
+        let val = DangerousOption::<i32>::new_uninitialized();
+        #[cfg(not(feature = "track-uninitialized"))]
+        assert_eq!(format!("{:?}", val), "<uninitialized>");
+        #[cfg(feature = "track-uninitialized")]
+        assert!(format!("{:?}", val).starts_with("<uninitialized at src/lib.rs:"));
+    }
+
+    #[test]
+    fn comparison() {
+        use ::{DangerousOption, ExceptionHandler};
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        enum Handler {}
+
+        impl ExceptionHandler for Handler {}
+
+        fn hash<T: Hash>(val: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            val.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let mut taken = DangerousOption::<i32, Handler>::new(47);
+        DangerousOption::take_unchecked(&mut taken);
+        let uninit = DangerousOption::<i32, Handler>::new_uninitialized();
+        let val = DangerousOption::<i32, Handler>::new(42);
+
+        assert_eq!(taken, uninit);
+        assert_eq!(hash(&taken), hash(&uninit));
+        assert!(uninit < val);
+        assert_eq!(val.clone(), val);
+        assert_eq!(hash(&val.clone()), hash(&val));
+        assert!(val < DangerousOption::new(47));
+    }
+
+    #[test]
+    fn conversions() {
+        use ::{DangerousOption, ExceptionHandler};
+
+        enum OtherHandler {}
+
+        impl ExceptionHandler for OtherHandler {}
+
+        let val: DangerousOption<i32> = 42.into();
+        assert_eq!(Option::from(val), Some(42));
+
+        let mut val: DangerousOption<i32> = Some(42).into();
+        *DangerousOption::as_option_mut(&mut val).unwrap() = 47;
+        assert_eq!(DangerousOption::as_option(&val), Some(&47));
+        DangerousOption::take_unchecked(&mut val);
+        let val = DangerousOption::with_handler::<OtherHandler>(val);
+        #[cfg(feature = "track-state")]
+        assert_eq!(DangerousOption::state(&val), ::State::Taken);
+        assert_eq!(DangerousOption::into_option(val), None);
+
+        let val: DangerousOption<i32> = None.into();
+        assert_eq!(DangerousOption::state(&val), ::State::Uninitialized);
+    }
+
+    #[test]
+    fn combinators() {
+        use ::DangerousOption;
+        use std::string::String;
+
+        let val = DangerousOption::<i32>::new(21);
+        assert!(DangerousOption::is_initialized(&val));
+        let mut val = DangerousOption::map(val, |val| val * 2);
+        assert_eq!(*val, 42);
+        assert_eq!(DangerousOption::take_if(&mut val, |val| *val > 50), None);
+        assert_eq!(DangerousOption::take_if(&mut val, |val| *val > 40), Some(42));
+        assert!(!DangerousOption::is_initialized(&val));
+        let val = DangerousOption::map(val, |val| val + 1);
+        assert!(!DangerousOption::is_initialized(&val));
+        assert_eq!(DangerousOption::ok_or(val, "uninitialized"), Err("uninitialized"));
+
+        let mut val = DangerousOption::<i32>::new_uninitialized();
+        assert!(!DangerousOption::is_initialized(&val));
+        assert_eq!(*DangerousOption::get_or_insert_with(&mut val, || 42), 42);
+        assert_eq!(*DangerousOption::get_or_insert_with(&mut val, || 47), 42);
+        assert_eq!(DangerousOption::replace(&mut val, 47), Some(42));
+        assert_eq!(DangerousOption::filter(val.clone(), |val| *val == 47), Some(47));
+        assert_eq!(DangerousOption::zip(val, DangerousOption::new("foo")), Some((47, "foo")));
+
+        let val = DangerousOption::<String>::new("foo".into());
+        assert_eq!(DangerousOption::as_deref(&val), Some("foo"));
+    }
+
+    #[test]
+    fn constant() {
+        use ::{DangerousOption, State};
+
+        const ANSWER: DangerousOption<i32> = DangerousOption::new(42);
+        static UNINIT: DangerousOption<i32> = DangerousOption::new_uninitialized();
+
+        assert_eq!(*ANSWER, 42);
+        assert_eq!(DangerousOption::state(&UNINIT), State::Uninitialized);
+    }
+
+    #[test]
+    fn replace_with() {
+        use ::DangerousOption;
+        use std::panic;
+
+        let mut val = DangerousOption::<i32>::new(21);
+        DangerousOption::replace_with(&mut val, |val| val * 2);
+        assert_eq!(*val, 42);
+
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| DangerousOption::replace_with(&mut val, |_| panic!("failed"))));
+        assert!(result.is_err());
+        assert!(!DangerousOption::is_initialized(&val));
+        #[cfg(feature = "track-state")]
+        {
+            assert_eq!(DangerousOption::state(&val), ::State::Lost);
+            let result = panic::catch_unwind(|| *val);
+            let message = result.unwrap_err().downcast::<std::string::String>().unwrap();
+            assert!(message.starts_with("Dereferenced DangerousOption<i32> after its value was lost during a panicking update"));
+        }
+
+        DangerousOption::put(&mut val, 47);
+        assert_eq!(*val, 47);
+    }
+
+    #[test]
+    #[cfg(feature = "track-state")]
+    fn poison() {
+        use ::{DangerousOption, State};
+        use std::panic;
+
+        let mut val = DangerousOption::<i32>::new(42);
+        assert_eq!(DangerousOption::update(&mut val, |val| { *val += 5; *val }), 47);
+        assert!(!DangerousOption::is_poisoned(&val));
+
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| DangerousOption::update(&mut val, |val| {
+            *val = 0;
+            panic!("failed");
+        })));
+        assert!(result.is_err());
+        assert_eq!(DangerousOption::state(&val), State::Poisoned);
+        let result = panic::catch_unwind(|| *val);
+        let message = result.unwrap_err().downcast::<std::string::String>().unwrap();
+        assert!(message.starts_with("Dereferenced poisoned DangerousOption<i32>"));
+
+        assert!(DangerousOption::clear_poison(&mut val));
+        assert_eq!(*val, 0);
+    }
+
+    #[test]
+    #[cfg(feature = "track-state")]
+    fn poison_recovery() {
+        use ::{DangerousOption, State};
+        use std::panic;
+
+        fn poisoned(val: i32) -> DangerousOption<i32> {
+            let mut val = DangerousOption::new(val);
+            let result = panic::catch_unwind(panic::AssertUnwindSafe(|| DangerousOption::update(&mut val, |_| panic!("failed"))));
+            assert!(result.is_err());
+            assert!(DangerousOption::is_poisoned(&val));
+            val
+        }
+
+        // borrowing fails
+        let mut val = poisoned(42);
+        assert!(DangerousOption::try(&val).is_none());
+        assert!(DangerousOption::get_mut(&mut val).is_err());
+        assert_ne!(val, DangerousOption::new(42));
+
+        // taking out or consuming recovers the value
+        assert_eq!(DangerousOption::take_checked(&mut val), Some(42));
+        assert_eq!(DangerousOption::state(&val), State::Taken);
+        assert_eq!(DangerousOption::take(&mut poisoned(42)).unwrap(), 42);
+        assert_eq!(DangerousOption::take_unchecked(&mut poisoned(42)), 42);
+        assert_eq!(DangerousOption::take_if(&mut poisoned(42), |val| *val == 42), Some(42));
+        assert_eq!(DangerousOption::put(&mut poisoned(42), 47), Some(42));
+        assert_eq!(DangerousOption::into_option(poisoned(42)), Some(42));
+        assert_eq!(DangerousOption::filter(poisoned(42), |val| *val == 42), Some(42));
+        assert_eq!(DangerousOption::zip(poisoned(42), DangerousOption::new(47)), Some((42, 47)));
+        assert_eq!(DangerousOption::ok_or(poisoned(42), ()), Ok(42));
+        assert_eq!(*DangerousOption::lend(&mut poisoned(42)), 42);
+
+        // get_or_insert_with neither borrows nor replaces it
+        let mut val = poisoned(42);
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| { DangerousOption::get_or_insert_with(&mut val, || 47); }));
+        assert!(result.is_err());
+        assert_eq!(DangerousOption::take_checked(&mut val), Some(42));
+
+        // mapping keeps the poisoning
+        let val = DangerousOption::map(poisoned(21), |val| val * 2);
+        assert!(DangerousOption::is_poisoned(&val));
+        assert_eq!(DangerousOption::into_option(val), Some(42));
+    }
+
+    #[test]
+    #[cfg(not(feature = "track-state"))]
+    fn update() {
+        use ::{DangerousOption, State};
+        use std::panic;
+
+        let mut val = DangerousOption::<i32>::new(42);
+        assert_eq!(DangerousOption::update(&mut val, |val| { *val += 5; *val }), 47);
+
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| DangerousOption::update(&mut val, |val| {
+            *val = 0;
+            panic!("failed");
+        })));
+        assert!(result.is_err());
+        assert_eq!(DangerousOption::state(&val), State::Uninitialized);
+        assert!(!DangerousOption::is_poisoned(&val));
+    }
+
+    #[test]
+    #[cfg(not(feature = "labels"))]
+    fn size() {
+        use ::DangerousOption;
+        use core::mem::size_of;
+        use std::boxed::Box;
+
+        #[cfg(not(feature = "track-state"))]
+        {
+            assert_eq!(size_of::<DangerousOption<&u8>>(), size_of::<&u8>());
+            assert_eq!(size_of::<DangerousOption<Box<u8>>>(), size_of::<Box<u8>>());
+            assert_eq!(size_of::<DangerousOption<core::num::NonZeroU32>>(), 4);
+            assert_eq!(size_of::<DangerousOption<u32>>(), size_of::<Option<u32>>());
+        }
+        #[cfg(all(feature = "track-state", not(feature = "track-uninitialized")))]
+        {
+            assert_eq!(size_of::<DangerousOption<Box<u8>>>(), 2 * size_of::<Box<u8>>());
+            assert_eq!(size_of::<DangerousOption<u32>>(), size_of::<Option<u32>>());
+        }
+        #[cfg(feature = "track-uninitialized")]
+        assert_eq!(size_of::<DangerousOption<Box<u8>>>(), 2 * size_of::<Box<u8>>());
+    }
+
+    #[test]
+    #[cfg(feature = "labels")]
+    fn label() {
+        use ::DangerousOption;
+        use std::format;
+        use std::string::ToString;
+
+        let mut val = DangerousOption::<i32>::new_uninitialized_labeled("db_pool");
+        assert_eq!(DangerousOption::label(&val), Some("db_pool"));
+        assert!(format!("{:?}", val).starts_with("<uninitialized `db_pool`"));
+        assert!(DangerousOption::take(&mut val).unwrap_err().to_string().starts_with("DangerousOption<i32> `db_pool` is uninitialized"));
+        let result = std::panic::catch_unwind(|| *val);
+        let message = result.unwrap_err().downcast::<std::string::String>().unwrap();
+        assert!(message.starts_with("Dereferenced uninitialized DangerousOption<i32> `db_pool` at "));
+    }
 }
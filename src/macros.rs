@@ -0,0 +1,108 @@
+//! Macros for declaring custom exception handlers.
+
+/// Declares an `ExceptionHandler` which panics with custom messages.
+///
+/// The first argument is the name of the generated type, optionally preceded by attributes and
+/// visibility. It's followed by `callback = "message"` pairs, where `callback` is the name of the
+/// `ExceptionHandler` method without the `bad_` prefix, or `handle`, which applies to all
+/// accesses without more specific message. The generated type overrides the callbacks that have a
+/// message, so like with the callbacks, `deref_taken` falls back to `deref` and `take_taken` falls
+/// back to `take`.
+///
+/// The generated handler calls the hook installed using `set_hook()`, logs the message at error
+/// level if the `log` feature is enabled and then panics with the message followed by the
+/// description of the access.
+///
+/// # Example
+///
+/// ```
+/// #[macro_use]
+/// extern crate dangerous_option;
+///
+/// use dangerous_option::DangerousOption;
+///
+/// exception_handler!(DbNotReady, deref = "database not connected yet", take = "database was already closed");
+///
+/// struct Pool;
+///
+/// # fn main() {
+/// let pool = DangerousOption::<Pool, DbNotReady>::new_uninitialized();
+/// let result = std::panic::catch_unwind(|| { let _ = &*pool; });
+/// assert!(result.is_err());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! exception_handler {
+    ($(#[$attr:meta])* $vis:vis $name:ident $(, $callback:ident = $message:expr)* $(,)?) => {
+        $(#[$attr])*
+        $vis enum $name {}
+
+        impl $crate::ExceptionHandler for $name {
+            #[track_caller]
+            fn handle(context: &$crate::AccessContext) -> ! {
+                let messages = $crate::__Messages { $($callback: Some($message),)* ..$crate::__Messages::NONE };
+                $crate::__handle_with_messages(&messages, context)
+            }
+
+            $($crate::exception_handler!(@callback $callback $message);)*
+        }
+    };
+    (@callback handle $message:expr) => {};
+    (@callback deref $message:expr) => {};
+    (@callback take $message:expr) => {};
+    (@callback deref_taken $message:expr) => { $crate::exception_handler!(@override bad_deref_taken $message); };
+    (@callback take_taken $message:expr) => { $crate::exception_handler!(@override bad_take_taken $message); };
+    (@callback reinit $message:expr) => { $crate::exception_handler!(@override bad_reinit $message); };
+    (@callback init_failed $message:expr) => { $crate::exception_handler!(@override bad_init_failed $message); };
+    (@callback lost $message:expr) => { $crate::exception_handler!(@override bad_lost $message); };
+    (@callback poisoned $message:expr) => { $crate::exception_handler!(@override bad_poisoned $message); };
+    (@callback sentinel $message:expr) => { $crate::exception_handler!(@override bad_sentinel $message); };
+    (@override $method:ident $message:expr) => {
+        #[track_caller]
+        fn $method(context: &$crate::AccessContext) -> ! {
+            $crate::__handle_with_message(Some($message), context)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use ::{DangerousOption, LateInit};
+
+    exception_handler!(DbNotReady, deref = "database not connected yet", take = "database was already closed", reinit = "database connected twice");
+
+    exception_handler! {
+        /// Reports everything the same way.
+        pub(crate) Everything,
+        handle = "something went wrong",
+    }
+
+    #[test]
+    #[should_panic(expected = "database not connected yet: Dereferenced uninitialized DangerousOption<i32> at src/macros.rs:")]
+    fn deref() {
+        let val = DangerousOption::<i32, DbNotReady>::new_uninitialized();
+        let _ = *val;
+    }
+
+    #[test]
+    #[should_panic(expected = "database was already closed: Attempt to take value from uninitialized DangerousOption<i32>")]
+    fn take() {
+        let mut val = DangerousOption::<i32, DbNotReady>::new_uninitialized();
+        DangerousOption::take_unchecked(&mut val);
+    }
+
+    #[test]
+    #[should_panic(expected = "database connected twice: ")]
+    fn reinit() {
+        let mut val = LateInit::<i32, DbNotReady>::new_uninitialized();
+        LateInit::init(&mut val, 1);
+        LateInit::init(&mut val, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "something went wrong: Attempt to take value from uninitialized DangerousOption<i32>")]
+    fn handle() {
+        let mut val = DangerousOption::<i32, Everything>::new_uninitialized();
+        DangerousOption::take_unchecked(&mut val);
+    }
+}
@@ -0,0 +1,186 @@
+//! Thread-safe value which is initialized exactly once.
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::panic::Location;
+use core::sync::atomic::{AtomicU8, Ordering};
+use ::{handler, Access, AccessContext, DefaultExceptionHandler, ExceptionHandler, State};
+
+const UNINITIALIZED: u8 = 0;
+const INITIALIZING: u8 = 1;
+const INITIALIZED: u8 = 2;
+
+/// Represents a value that is initialized exactly once and then shared between threads.
+///
+/// This is a `Sync` version of `LateInit`, intended mainly for `static`s which are filled once
+/// at the beginning of the program. It's implemented using atomics only, so it works in `no_std`.
+/// It's only available on targets supporting atomic compare-and-swap.
+///
+/// Dereferencing uninitialized value calls the ExceptionHandler. Attempt to set the value again,
+/// including two threads racing to set it, calls `ExceptionHandler::bad_reinit()`. Value which is
+/// being set concurrently with dereferencing counts as uninitialized.
+pub struct DangerousOnce<T, H: ExceptionHandler = DefaultExceptionHandler> {
+    state: AtomicU8,
+    value: UnsafeCell<MaybeUninit<T>>,
+    _handler: core::marker::PhantomData<H>,
+}
+
+// The value is only written once, by the thread which won the race to set it, and only read after
+// the write was published with Release store.
+unsafe impl<T: Send + Sync, H: ExceptionHandler> Sync for DangerousOnce<T, H> {}
+
+impl<T, H: ExceptionHandler> DangerousOnce<T, H> {
+    /// Creates initialized value.
+    pub const fn new(val: T) -> Self {
+        DangerousOnce {
+            state: AtomicU8::new(INITIALIZED),
+            value: UnsafeCell::new(MaybeUninit::new(val)),
+            _handler: core::marker::PhantomData,
+        }
+    }
+
+    /// Creates uninitialized value.
+    pub const fn new_uninitialized() -> Self {
+        DangerousOnce {
+            state: AtomicU8::new(UNINITIALIZED),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            _handler: core::marker::PhantomData,
+        }
+    }
+
+    /// Initializes the value, failing if it was already initialized or is being initialized by
+    /// another thread.
+    #[track_caller]
+    pub fn set(this: &Self, val: T) {
+        if this.state.compare_exchange(UNINITIALIZED, INITIALIZING, Ordering::Acquire, Ordering::Acquire).is_err() {
+            this.fail(Access::Init, State::Initialized);
+        }
+
+        // We've won the race, so nobody else accesses the value.
+        unsafe {
+            (*this.value.get()).as_mut_ptr().write(val);
+        }
+        this.state.store(INITIALIZED, Ordering::Release);
+    }
+
+    /// Returns `true` if the value is initialized.
+    pub fn is_initialized(this: &Self) -> bool {
+        this.state.load(Ordering::Acquire) == INITIALIZED
+    }
+
+    /// Non-panicking version of deref, which returns `None`, if value is uninitialized.
+    pub fn try(this: &Self) -> Option<&T> {
+        if DangerousOnce::is_initialized(this) {
+            // Initialized value is never written again and Acquire load synchronized with the
+            // Release store in set().
+            unsafe { Some(&*(*this.value.get()).as_ptr()) }
+        } else {
+            None
+        }
+    }
+
+    /// Non-panicking version of deref_mut, which returns `None`, if value is uninitialized.
+    pub fn try_mut(this: &mut Self) -> Option<&mut T> {
+        if *this.state.get_mut() == INITIALIZED {
+            // We have exclusive access.
+            unsafe { Some(&mut *(*this.value.get()).as_mut_ptr()) }
+        } else {
+            None
+        }
+    }
+
+    #[track_caller]
+    fn fail(&self, access: Access, state: State) -> ! {
+        let context = AccessContext::new::<T>("DangerousOnce", access, Location::caller(), state, None);
+        handler::dispatch::<H>(&context)
+    }
+}
+
+impl<T, H: ExceptionHandler> core::ops::Deref for DangerousOnce<T, H> {
+    type Target = T;
+
+    #[track_caller]
+    fn deref(&self) -> &Self::Target {
+        match DangerousOnce::try(self) {
+            Some(val) => val,
+            None => self.fail(Access::Deref, State::Uninitialized),
+        }
+    }
+}
+
+impl<T, H: ExceptionHandler> core::ops::DerefMut for DangerousOnce<T, H> {
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if *self.state.get_mut() != INITIALIZED {
+            self.fail(Access::DerefMut, State::Uninitialized);
+        }
+        // We have exclusive access and the value is initialized.
+        unsafe { &mut *(*self.value.get()).as_mut_ptr() }
+    }
+}
+
+impl<T, H: ExceptionHandler> Drop for DangerousOnce<T, H> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == INITIALIZED {
+            unsafe {
+                core::ptr::drop_in_place((*self.value.get()).as_mut_ptr());
+            }
+        }
+    }
+}
+
+impl<T: core::fmt::Debug, H: ExceptionHandler> core::fmt::Debug for DangerousOnce<T, H> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match DangerousOnce::try(self) {
+            Some(val) => core::fmt::Debug::fmt(val, f),
+            None => write!(f, "<uninitialized>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::DangerousOnce;
+
+    static VALUE: DangerousOnce<u32> = DangerousOnce::new_uninitialized();
+
+    #[test]
+    fn threads() {
+        use std::thread;
+        use std::vec::Vec;
+
+        assert!(DangerousOnce::try(&VALUE).is_none());
+        DangerousOnce::set(&VALUE, 42);
+        let threads = (0..4).map(|_| thread::spawn(|| *VALUE)).collect::<Vec<_>>();
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), 42);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempt to initialize already initialized DangerousOnce<u32>")]
+    fn reinit() {
+        let val = DangerousOnce::<u32>::new(42);
+        DangerousOnce::set(&val, 47);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dereferenced uninitialized DangerousOnce<u32>")]
+    fn uninitialized() {
+        let val = DangerousOnce::<u32>::new_uninitialized();
+        let _ = *val;
+    }
+
+    #[test]
+    fn drop() {
+        use std::rc::Rc;
+
+        let rc = Rc::new(());
+        let val = DangerousOnce::<Rc<()>>::new_uninitialized();
+        DangerousOnce::set(&val, rc.clone());
+        assert_eq!(Rc::strong_count(&rc), 2);
+        core::mem::drop(val);
+        assert_eq!(Rc::strong_count(&rc), 1);
+        core::mem::drop(DangerousOnce::<Rc<()>>::new_uninitialized());
+    }
+}
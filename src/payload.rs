@@ -0,0 +1,68 @@
+//! Typed panic payload for invalid accesses.
+
+use std::fmt;
+use std::panic;
+use ::{handler, AccessContext, ExceptionHandler};
+
+/// The panic payload used by `PayloadExceptionHandler`.
+///
+/// It can be downcast from the payload returned by `std::panic::catch_unwind()` in order to
+/// recognize and report invalid accesses specifically.
+#[derive(Debug, Copy, Clone)]
+pub struct DangerousAccessError {
+    context: AccessContext,
+}
+
+impl DangerousAccessError {
+    /// Describes the invalid access - its kind, type name, location and provenance.
+    pub fn context(&self) -> &AccessContext {
+        &self.context
+    }
+}
+
+impl fmt::Display for DangerousAccessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.context, f)
+    }
+}
+
+impl std::error::Error for DangerousAccessError {}
+
+/// Exception handler which panics with `DangerousAccessError` payload using
+/// `std::panic::panic_any()`.
+///
+/// The hook installed using `set_hook()` is called before panicking. Note that the default panic
+/// hook of `std` doesn't print the message of typed payloads.
+///
+/// Requires the `std` feature.
+pub enum PayloadExceptionHandler {}
+
+impl ExceptionHandler for PayloadExceptionHandler {
+    fn handle(context: &AccessContext) -> ! {
+        if let Some(hook) = handler::hook() {
+            hook(context);
+        }
+        panic::panic_any(DangerousAccessError { context: *context })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn payload() {
+        use ::{Access, DangerousAccessError, DangerousOption, PayloadExceptionHandler, State};
+        use std::panic;
+
+        let mut val = DangerousOption::<i32, PayloadExceptionHandler>::new(42);
+        DangerousOption::take_unchecked(&mut val);
+        let (result, line) = (panic::catch_unwind(|| *val), line!());
+        let error = result.unwrap_err().downcast::<DangerousAccessError>().unwrap();
+        assert_eq!(error.context().access(), Access::Deref);
+        #[cfg(feature = "track-state")]
+        assert_eq!(error.context().state(), State::Taken);
+        #[cfg(not(feature = "track-state"))]
+        assert_eq!(error.context().state(), State::Uninitialized);
+        assert_eq!(error.context().type_name(), "i32");
+        assert_eq!(error.context().location().line(), line);
+    }
+}
@@ -0,0 +1,232 @@
+//! Uninitialized state represented by a reserved value.
+
+use core::panic::Location;
+use ::{handler, Access, AccessContext, DefaultExceptionHandler, ExceptionHandler, State};
+
+/// Defines a value of `T` which represents uninitialized state of `DangerousSentinel`.
+///
+/// The sentinel must be a value that never occurs as valid value in the program. Implementors are
+/// usually uninhabited enums, like `MaxValue` or `NullPtr`.
+pub trait Sentinel<T> {
+    /// The value representing uninitialized state.
+    const SENTINEL: T;
+
+    /// Returns `true` if `value` is the sentinel.
+    fn is_sentinel(value: &T) -> bool;
+}
+
+/// Uses the maximum value of an integer type as the sentinel.
+pub enum MaxValue {}
+
+macro_rules! impl_max_value {
+    ($($type:ty),*) => {
+        $(
+            impl Sentinel<$type> for MaxValue {
+                const SENTINEL: $type = <$type>::MAX;
+
+                fn is_sentinel(value: &$type) -> bool {
+                    *value == <$type>::MAX
+                }
+            }
+        )*
+    }
+}
+
+impl_max_value!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// Uses null pointer as the sentinel.
+pub enum NullPtr {}
+
+impl<T> Sentinel<*const T> for NullPtr {
+    const SENTINEL: *const T = core::ptr::null();
+
+    fn is_sentinel(value: &*const T) -> bool {
+        value.is_null()
+    }
+}
+
+impl<T> Sentinel<*mut T> for NullPtr {
+    const SENTINEL: *mut T = core::ptr::null_mut();
+
+    fn is_sentinel(value: &*mut T) -> bool {
+        value.is_null()
+    }
+}
+
+/// Represents a value that might be uninitialized, using a reserved value of `T` to mark
+/// uninitialized state.
+///
+/// This has the same size as `T`, unlike `DangerousOption`, which is bigger for types without a
+/// niche. Otherwise it behaves the same: dereferencing uninitialized value calls the
+/// ExceptionHandler. Attempt to put the sentinel into it calls
+/// `ExceptionHandler::bad_sentinel()`. Note that writing the sentinel through mutable reference
+/// makes the value uninitialized.
+///
+/// Since only the value is stored, this type doesn't distinguish between never initialized and
+/// taken out values and doesn't track where it became uninitialized.
+pub struct DangerousSentinel<T, S: Sentinel<T>, H: ExceptionHandler = DefaultExceptionHandler> {
+    value: T,
+    _sentinel: core::marker::PhantomData<S>,
+    _handler: core::marker::PhantomData<H>,
+}
+
+impl<T, S: Sentinel<T>, H: ExceptionHandler> DangerousSentinel<T, S, H> {
+    /// Creates valid value, failing if `val` is the sentinel.
+    #[track_caller]
+    pub fn new(val: T) -> Self {
+        if S::is_sentinel(&val) {
+            Self::fail(Access::PutSentinel, State::Uninitialized);
+        }
+        DangerousSentinel { value: val, _sentinel: core::marker::PhantomData, _handler: core::marker::PhantomData }
+    }
+
+    /// Creates uninitialized value.
+    pub const fn new_uninitialized() -> Self {
+        DangerousSentinel { value: S::SENTINEL, _sentinel: core::marker::PhantomData, _handler: core::marker::PhantomData }
+    }
+
+    /// Takes out the value, failing if it's not there. After call to this function, the value is
+    /// uninitialized.
+    #[track_caller]
+    pub fn take_unchecked(this: &mut Self) -> T {
+        match DangerousSentinel::take_checked(this) {
+            Some(val) => val,
+            None => Self::fail(Access::Take, State::Uninitialized),
+        }
+    }
+
+    /// Tries to take out the value. After call to this function, the value is uninitialized.
+    pub fn take_checked(this: &mut Self) -> Option<T> {
+        if DangerousSentinel::is_initialized(this) {
+            Some(core::mem::replace(&mut this.value, S::SENTINEL))
+        } else {
+            None
+        }
+    }
+
+    /// Non-panicking version of deref, which returns `None`, if value is uninitialized.
+    pub fn try(this: &Self) -> Option<&T> {
+        if DangerousSentinel::is_initialized(this) {
+            Some(&this.value)
+        } else {
+            None
+        }
+    }
+
+    /// Non-panicking version of deref_mut, which returns `None`, if value is uninitialized.
+    pub fn try_mut(this: &mut Self) -> Option<&mut T> {
+        if DangerousSentinel::is_initialized(this) {
+            Some(&mut this.value)
+        } else {
+            None
+        }
+    }
+
+    /// Puts the new value in place of old, optionally returning old value. Fails if `val` is the
+    /// sentinel.
+    #[track_caller]
+    pub fn put(this: &mut Self, val: T) -> Option<T> {
+        if S::is_sentinel(&val) {
+            Self::fail(Access::PutSentinel, DangerousSentinel::state(this));
+        }
+        let old = core::mem::replace(&mut this.value, val);
+        if S::is_sentinel(&old) {
+            None
+        } else {
+            Some(old)
+        }
+    }
+
+    /// Returns `true` if the value is initialized.
+    pub fn is_initialized(this: &Self) -> bool {
+        !S::is_sentinel(&this.value)
+    }
+
+    fn state(this: &Self) -> State {
+        if DangerousSentinel::is_initialized(this) {
+            State::Initialized
+        } else {
+            State::Uninitialized
+        }
+    }
+
+    #[track_caller]
+    fn fail(access: Access, state: State) -> ! {
+        let context = AccessContext::new::<T>("DangerousSentinel", access, Location::caller(), state, None);
+        handler::dispatch::<H>(&context)
+    }
+}
+
+impl<T, S: Sentinel<T>, H: ExceptionHandler> core::ops::Deref for DangerousSentinel<T, S, H> {
+    type Target = T;
+
+    #[track_caller]
+    fn deref(&self) -> &Self::Target {
+        if !DangerousSentinel::is_initialized(self) {
+            Self::fail(Access::Deref, State::Uninitialized);
+        }
+        &self.value
+    }
+}
+
+impl<T, S: Sentinel<T>, H: ExceptionHandler> core::ops::DerefMut for DangerousSentinel<T, S, H> {
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if !DangerousSentinel::is_initialized(self) {
+            Self::fail(Access::DerefMut, State::Uninitialized);
+        }
+        &mut self.value
+    }
+}
+
+impl<T: Clone, S: Sentinel<T>, H: ExceptionHandler> Clone for DangerousSentinel<T, S, H> {
+    fn clone(&self) -> Self {
+        DangerousSentinel { value: self.value.clone(), _sentinel: core::marker::PhantomData, _handler: core::marker::PhantomData }
+    }
+}
+
+impl<T: core::fmt::Debug, S: Sentinel<T>, H: ExceptionHandler> core::fmt::Debug for DangerousSentinel<T, S, H> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match DangerousSentinel::try(self) {
+            Some(val) => core::fmt::Debug::fmt(val, f),
+            None => write!(f, "<uninitialized>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::{DangerousSentinel, MaxValue, NullPtr};
+
+    #[test]
+    fn sentinel() {
+        let mut val = DangerousSentinel::<u32, MaxValue>::new_uninitialized();
+        assert!(DangerousSentinel::try(&val).is_none());
+        assert_eq!(DangerousSentinel::put(&mut val, 42), None);
+        *val += 5;
+        assert_eq!(DangerousSentinel::take_unchecked(&mut val), 47);
+        assert!(!DangerousSentinel::is_initialized(&val));
+
+        let val = DangerousSentinel::<*const u8, NullPtr>::new_uninitialized();
+        assert!(DangerousSentinel::try(&val).is_none());
+    }
+
+    #[test]
+    fn size() {
+        assert_eq!(core::mem::size_of::<DangerousSentinel<u32, MaxValue>>(), core::mem::size_of::<u32>());
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempt to put sentinel value into uninitialized DangerousSentinel<u32>")]
+    fn put_sentinel() {
+        let mut val = DangerousSentinel::<u32, MaxValue>::new_uninitialized();
+        DangerousSentinel::put(&mut val, u32::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dereferenced uninitialized DangerousSentinel<u32>")]
+    fn uninitialized() {
+        let val = DangerousSentinel::<u32, MaxValue>::new_uninitialized();
+        let _ = *val;
+    }
+}
@@ -0,0 +1,115 @@
+//! Serde support for `DangerousOption`.
+//!
+//! Initialized `DangerousOption<T, H>` is serialized the same way as `Some(T)`. In self-describing
+//! formats like JSON, YAML or TOML, this is the same as `T`. In other formats, like bincode or
+//! postcard, it's prefixed with the tag of `Option`, so that it can be deserialized back. Attempt
+//! to serialize uninitialized value calls the ExceptionHandler, same as dereferencing it would. If
+//! `null` should be emitted instead, use
+//! `#[serde(with = "dangerous_option::serialization::or_null")]` on the field.
+//!
+//! Deserialization accepts `Option<T>`, which is `T` or `null` in self-describing formats. Both
+//! `null` and missing field deserialize into uninitialized value, so that late-initialized fields
+//! don't have to be present in the input. Note that serde requires
+//! `#[serde(default = "DangerousOption::new_uninitialized")]` to handle missing fields when
+//! `with` is used.
+//!
+//! Since `null` deserializes into uninitialized value, `DangerousOption<Option<U>>` doesn't
+//! round-trip: `DangerousOption::new(None)` is serialized as `null`, which deserializes into
+//! uninitialized value, not into `DangerousOption::new(None)`.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use ::{Access, DangerousOption, ExceptionHandler};
+
+impl<T: Serialize, H: ExceptionHandler> Serialize for DangerousOption<T, H> {
+    #[track_caller]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match DangerousOption::try(self) {
+            Some(val) => serializer.serialize_some(val),
+            None => self.fail(Access::Serialize),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>, H: ExceptionHandler> Deserialize<'de> for DangerousOption<T, H> {
+    #[track_caller]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Option::deserialize(deserializer)? {
+            Some(val) => Ok(DangerousOption::new(val)),
+            None => Ok(DangerousOption::new_uninitialized()),
+        }
+    }
+}
+
+/// Serializes uninitialized `DangerousOption` as `null` instead of calling the ExceptionHandler.
+///
+/// Use `#[serde(with = "dangerous_option::serialization::or_null", default = "DangerousOption::new_uninitialized")]`
+pub mod or_null {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use ::{DangerousOption, ExceptionHandler};
+
+    /// Serializes the value or `null` if it's uninitialized.
+    pub fn serialize<T: Serialize, H: ExceptionHandler, S: Serializer>(value: &DangerousOption<T, H>, serializer: S) -> Result<S::Ok, S::Error> {
+        DangerousOption::try(value).serialize(serializer)
+    }
+
+    /// Deserializes the value, turning `null` into uninitialized value.
+    #[track_caller]
+    pub fn deserialize<'de, T: Deserialize<'de>, H: ExceptionHandler, D: Deserializer<'de>>(deserializer: D) -> Result<DangerousOption<T, H>, D::Error> {
+        DangerousOption::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::DangerousOption;
+
+    #[derive(Serialize, Deserialize)]
+    struct Config {
+        name: DangerousOption<u32>,
+        #[serde(with = "::serialization::or_null", default = "DangerousOption::new_uninitialized")]
+        port: DangerousOption<u16>,
+    }
+
+    #[test]
+    fn round_trip() {
+        let config: Config = ::serde_json::from_str(r#"{"name":42,"port":null}"#).unwrap();
+        assert_eq!(*config.name, 42);
+        assert!(DangerousOption::try(&config.port).is_none());
+        assert_eq!(::serde_json::to_string(&config).unwrap(), r#"{"name":42,"port":null}"#);
+
+        let config: Config = ::serde_json::from_str(r#"{"port":80}"#).unwrap();
+        assert!(DangerousOption::try(&config.name).is_none());
+        assert_eq!(*config.port, 80);
+
+        let config: Config = ::serde_json::from_str("{}").unwrap();
+        assert!(DangerousOption::try(&config.port).is_none());
+    }
+
+    #[test]
+    fn non_self_describing() {
+        let config = Config { name: DangerousOption::new(42), port: DangerousOption::new_uninitialized() };
+        let bytes = ::bincode::serialize(&config).unwrap();
+        let config: Config = ::bincode::deserialize(&bytes).unwrap();
+        assert_eq!(*config.name, 42);
+        assert!(DangerousOption::try(&config.port).is_none());
+    }
+
+    #[test]
+    fn nested_option() {
+        let val = DangerousOption::<Option<u32>>::new(None);
+        let json = ::serde_json::to_string(&val).unwrap();
+        assert_eq!(json, "null");
+        let val: DangerousOption<Option<u32>> = ::serde_json::from_str(&json).unwrap();
+        assert!(!DangerousOption::is_initialized(&val));
+
+        let val: DangerousOption<Option<u32>> = ::serde_json::from_str("42").unwrap();
+        assert_eq!(*val, Some(42));
+    }
+
+    #[test]
+    #[should_panic(expected = "Serialized uninitialized DangerousOption<u32>")]
+    fn uninitialized() {
+        let config = Config { name: DangerousOption::new_uninitialized(), port: DangerousOption::new(80) };
+        let _ = ::serde_json::to_string(&config);
+    }
+}
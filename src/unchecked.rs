@@ -0,0 +1,197 @@
+//! Value whose initialization is checked only in debug builds.
+
+use core::mem::MaybeUninit;
+use ::{Access, DefaultExceptionHandler, ExceptionHandler};
+#[cfg(debug_assertions)]
+use core::panic::Location;
+#[cfg(debug_assertions)]
+use ::{handler, AccessContext, State};
+
+/// Represents a value that might be uninitialized, without paying for checking it in release
+/// builds.
+///
+/// In debug builds (with `debug_assertions`), this type tracks whether the value is initialized
+/// and calls the ExceptionHandler on invalid access, just like `DangerousOption`. In release
+/// builds, it's just `MaybeUninit<T>` - it has the same size as `T` and dereferencing doesn't
+/// branch.
+///
+/// # Safety contract
+///
+/// The only ways to make the value uninitialized are the `unsafe` functions `new_uninitialized()`
+/// and `take_unchecked()`. After calling any of them, the caller must initialize the value using
+/// `put()` before it's accessed in any other way, including dereferencing, formatting and
+/// dropping - even if a panic happens in between. Violating this is undefined behavior in release
+/// builds.
+pub struct DangerousUnchecked<T, H: ExceptionHandler = DefaultExceptionHandler> {
+    value: MaybeUninit<T>,
+    #[cfg(debug_assertions)]
+    initialized: bool,
+    _handler: core::marker::PhantomData<H>,
+}
+
+impl<T, H: ExceptionHandler> DangerousUnchecked<T, H> {
+    /// Creates initialized value.
+    pub const fn new(val: T) -> Self {
+        DangerousUnchecked {
+            value: MaybeUninit::new(val),
+            #[cfg(debug_assertions)]
+            initialized: true,
+            _handler: core::marker::PhantomData,
+        }
+    }
+
+    /// Creates uninitialized value.
+    ///
+    /// # Safety
+    ///
+    /// The value must be initialized using `put()` before it's accessed or dropped.
+    pub const unsafe fn new_uninitialized() -> Self {
+        DangerousUnchecked {
+            value: MaybeUninit::uninit(),
+            #[cfg(debug_assertions)]
+            initialized: false,
+            _handler: core::marker::PhantomData,
+        }
+    }
+
+    /// Takes out the value. After call to this function, the value is uninitialized.
+    ///
+    /// # Safety
+    ///
+    /// The value must be initialized using `put()` before it's accessed or dropped.
+    #[track_caller]
+    pub unsafe fn take_unchecked(this: &mut Self) -> T {
+        this.check(Access::Take);
+        #[cfg(debug_assertions)]
+        {
+            this.initialized = false;
+        }
+        this.value.as_ptr().read()
+    }
+
+    /// Initializes the value.
+    ///
+    /// This is intended to be called only after `new_uninitialized()` or `take_unchecked()`. If
+    /// the value is already initialized, the old value is leaked in release builds and
+    /// `ExceptionHandler::bad_reinit()` is called in debug builds. Use `replace()` to change
+    /// initialized value.
+    #[track_caller]
+    pub fn put(this: &mut Self, val: T) {
+        #[cfg(debug_assertions)]
+        {
+            if this.initialized {
+                this.fail(Access::Init, State::Initialized);
+            }
+            this.initialized = true;
+        }
+        this.value = MaybeUninit::new(val);
+    }
+
+    /// Replaces the value, returning the old one.
+    #[track_caller]
+    pub fn replace(this: &mut Self, val: T) -> T {
+        core::mem::replace(&mut **this, val)
+    }
+
+    /// Checks that the value is initialized in debug builds.
+    #[track_caller]
+    #[inline]
+    fn check(&self, access: Access) {
+        #[cfg(debug_assertions)]
+        {
+            if !self.initialized {
+                self.fail(access, State::Uninitialized);
+            }
+        }
+        #[cfg(not(debug_assertions))]
+        {
+            let _ = access;
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[track_caller]
+    fn fail(&self, access: Access, state: State) -> ! {
+        let context = AccessContext::new::<T>("DangerousUnchecked", access, Location::caller(), state, None);
+        handler::dispatch::<H>(&context)
+    }
+}
+
+impl<T, H: ExceptionHandler> core::ops::Deref for DangerousUnchecked<T, H> {
+    type Target = T;
+
+    #[track_caller]
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.check(Access::Deref);
+        // The value is initialized according to the safety contract.
+        unsafe { &*self.value.as_ptr() }
+    }
+}
+
+impl<T, H: ExceptionHandler> core::ops::DerefMut for DangerousUnchecked<T, H> {
+    #[track_caller]
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.check(Access::DerefMut);
+        // The value is initialized according to the safety contract.
+        unsafe { &mut *self.value.as_mut_ptr() }
+    }
+}
+
+impl<T, H: ExceptionHandler> Drop for DangerousUnchecked<T, H> {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        {
+            if !self.initialized {
+                return;
+            }
+        }
+        // The value is initialized according to the safety contract.
+        unsafe {
+            core::ptr::drop_in_place(self.value.as_mut_ptr());
+        }
+    }
+}
+
+impl<T: core::fmt::Debug, H: ExceptionHandler> core::fmt::Debug for DangerousUnchecked<T, H> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        #[cfg(debug_assertions)]
+        {
+            if !self.initialized {
+                return write!(f, "<uninitialized>");
+            }
+        }
+        core::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::DangerousUnchecked;
+
+    #[test]
+    fn unchecked() {
+        let mut val = DangerousUnchecked::<i32>::new(42);
+        *val += 5;
+        let taken = unsafe { DangerousUnchecked::take_unchecked(&mut val) };
+        assert_eq!(taken, 47);
+        DangerousUnchecked::put(&mut val, 42);
+        assert_eq!(DangerousUnchecked::replace(&mut val, 47), 42);
+        assert_eq!(*val, 47);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Dereferenced uninitialized DangerousUnchecked<i32>")]
+    fn uninitialized() {
+        let val = unsafe { DangerousUnchecked::<i32>::new_uninitialized() };
+        let _ = *val;
+    }
+
+    #[test]
+    #[cfg(not(debug_assertions))]
+    fn size() {
+        assert_eq!(core::mem::size_of::<DangerousUnchecked<u32>>(), core::mem::size_of::<u32>());
+    }
+}